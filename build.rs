@@ -1,13 +1,21 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn main() {
     println!("cargo:rerun-if-changed=wrapper.h");
     println!("cargo:rerun-if-changed=lonesha256.h");
     println!("cargo:rerun-if-changed=src/wrapper.c");
+    println!("cargo:rerun-if-changed=src/headless.c");
+    println!("cargo:rerun-if-changed=src/headless.h");
 
-    // Link neccessary system libraries
-    if cfg!(target_os = "windows") {
+    let headless = env::var_os("CARGO_FEATURE_HEADLESS").is_some();
+
+    // Link neccessary system libraries, the headless backend needs none of them
+    if headless {
+        if cfg!(target_os = "linux") {
+            println!("cargo:rustc-link-lib=pthread");
+        }
+    } else if cfg!(target_os = "windows") {
         println!("cargo:rustc-link-lib=user32");
         println!("cargo:rustc-link-lib=gdi32");
         println!("cargo:rustc-link-lib=shell32");
@@ -19,14 +27,53 @@ fn main() {
     }
 
     // Compile C library
-    cc::Build::new()
-        .file("src/wrapper.c")
-        .include(".")
-        .compile("luigi");
+    let mut build = cc::Build::new();
+    build.file("src/wrapper.c").include(".");
+
+    // Defines that change struct layouts must be seen by bindgen too
+    let mut clang_args = Vec::new();
+
+    if env::var_os("CARGO_FEATURE_FREETYPE").is_some() {
+        println!("cargo:rerun-if-env-changed=LUIGI_FONT_PATH");
+        println!("cargo:rerun-if-env-changed=FREETYPE_INCLUDE_DIR");
+        println!("cargo:rustc-link-lib=freetype");
+
+        // Luigi stringifies the path itself, so it is passed without quotes
+        let font_path = env::var("LUIGI_FONT_PATH").unwrap_or_else(|_| default_font_path());
+        // Also seen by `init`, which loads the default font at a larger size
+        println!("cargo:rustc-env=LUIGI_RS_FONT_PATH={}", font_path);
+        let include_dir = env::var("FREETYPE_INCLUDE_DIR")
+            .unwrap_or_else(|_| "/usr/include/freetype2".to_string());
+
+        build
+            .define("UI_FREETYPE", None)
+            .define("UI_FONT_PATH", font_path.as_str())
+            .include(&include_dir);
+        clang_args.push("-DUI_FREETYPE".to_string());
+        clang_args.push(format!("-I{}", include_dir));
+    }
+
+    if env::var_os("CARGO_FEATURE_DEBUG").is_some() {
+        build.define("UI_DEBUG", None);
+        clang_args.push("-DUI_DEBUG".to_string());
+    }
+
+    if env::var_os("CARGO_FEATURE_AUTOMATION").is_some() {
+        build.define("UI_AUTOMATION_TESTS", None);
+        clang_args.push("-DUI_AUTOMATION_TESTS".to_string());
+    }
+
+    if headless {
+        build.define("UI_HEADLESS", None);
+        clang_args.push("-DUI_HEADLESS".to_string());
+    }
+
+    build.compile("luigi");
 
     // Generate bindings
     let bindings = bindgen::Builder::default()
         .header("wrapper.h")
+        .clang_args(&clang_args)
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         .generate()
         .expect("Unable to generate bindings");
@@ -37,3 +84,27 @@ fn main() {
         .write_to_file(out_path.join("ffi.rs"))
         .expect("Couldn't write bindings!");
 }
+
+/// Find a monospaced font in the usual install locations
+fn default_font_path() -> String {
+    let candidates: &[&str] = if cfg!(target_os = "windows") {
+        &["C:/Windows/Fonts/consola.ttf"]
+    } else {
+        &[
+            // Arch
+            "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+            // Debian and Ubuntu
+            "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+            // Fedora
+            "/usr/share/fonts/dejavu/DejaVuSansMono.ttf",
+            "/usr/share/fonts/dejavu-sans-mono-fonts/DejaVuSansMono.ttf",
+        ]
+    };
+    match candidates.iter().find(|path| Path::new(path).is_file()) {
+        Some(path) => path.to_string(),
+        None => panic!(
+            "no default font found in {:?}, set LUIGI_FONT_PATH to a TrueType font file",
+            candidates
+        ),
+    }
+}
@@ -0,0 +1,49 @@
+use luigi_rs::{self as ui, Button, Element, Label, Menu, Panel, Window};
+use std::thread;
+use std::time::Duration;
+
+fn main() {
+    ui::init();
+
+    let window = Window::new("Async", 300, 150, 0).expect("Failed to create window");
+    let panel = Panel::new(&window, ui::UI_PANEL_GRAY | ui::UI_PANEL_MEDIUM_SPACING)
+        .expect("Failed to create panel");
+    let button = Button::new(&panel, 0, "Compute").expect("Failed to create button");
+    let mut label = Label::new(&panel, 0, "Click the button").expect("Failed to create label");
+
+    let mut clicks = button.clicks().expect("Failed to listen for clicks");
+    window
+        .spawn_local(async move {
+            while clicks.next().await.is_some() {
+                let mut menu = Menu::new(&button, 0).expect("Failed to create menu");
+                for name in ["Fast", "Slow"] {
+                    menu.add_item(0, name, Box::new(|| {}))
+                        .expect("Failed to add item");
+                }
+                let Some(choice) = menu.show_async().expect("Failed to show menu").await else {
+                    continue;
+                };
+
+                label
+                    .set_content("Working...")
+                    .expect("Failed to update label");
+                label.refresh().expect("Failed to refresh label");
+
+                // The UI keeps running while the thread sleeps
+                let delay = Duration::from_millis(if choice == 0 { 200 } else { 2000 });
+                let answer = ui::spawn_blocking(move || {
+                    thread::sleep(delay);
+                    42
+                })
+                .await;
+
+                label
+                    .set_content(&format!("The answer is {answer}"))
+                    .expect("Failed to update label");
+                label.refresh().expect("Failed to refresh label");
+            }
+        })
+        .expect("Failed to spawn task");
+
+    ui::message_loop();
+}
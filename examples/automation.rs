@@ -0,0 +1,48 @@
+use luigi_rs::{self as ui, Automation, Button, Code, Key, Panel, TextBox, Widget, Window};
+
+// Run with `cargo run --example automation --features automation`
+fn main() {
+    ui::init();
+
+    let window = Window::new("Automation", 400, 300, 0).expect("Failed to create window");
+    let panel = Panel::new(&window, ui::UI_PANEL_GRAY | ui::UI_PANEL_MEDIUM_SPACING)
+        .expect("Failed to create panel");
+    let textbox = TextBox::new(&panel, ui::UI_ELEMENT_H_FILL).expect("Failed to create textbox");
+    let button = Button::new(&panel, 0, "Add line").expect("Failed to create button");
+    let code = Code::new(&panel, ui::UI_ELEMENT_H_FILL | ui::UI_ELEMENT_V_FILL)
+        .expect("Failed to create code view");
+
+    // The button appends the textbox contents to the code view
+    let textbox_handle = textbox.handle();
+    let code_handle = code.handle();
+    button
+        .invoke(Box::new(move || {
+            let textbox = textbox_handle.upgrade().expect("Textbox is gone");
+            let mut code = code_handle.upgrade().expect("Code view is gone");
+            let line = textbox.get_text().expect("Failed to get text");
+            code.insert_content(&line, false)
+                .expect("Failed to insert line");
+        }))
+        .expect("Failed to set callback");
+
+    // These run instead of the event loop, with the window already laid out
+    Automation::set_tests(move |automation| {
+        automation.click(&textbox).expect("Failed to click textbox");
+        automation.type_text("Hello").expect("Failed to type");
+        automation.click(&button).expect("Failed to click button");
+        automation.assert_code_line(&code, 1, "Hello");
+
+        // Select everything and replace it
+        automation
+            .press_shortcut(Key::Letter('A'), true, false, false)
+            .expect("Failed to press shortcut");
+        automation.type_text("World").expect("Failed to type");
+        automation.click(&button).expect("Failed to click button");
+        automation.assert_code_line(&code, 2, "World");
+
+        println!("All checks passed");
+        0
+    });
+
+    std::process::exit(ui::message_loop());
+}
@@ -30,22 +30,29 @@ fn main() {
         *count_clone.borrow_mut() -= 1;
         let mut label = label_clone.borrow_mut();
         // Format the number into a string with proper width
-        label.set_content(&format!("{:>3}", *count_clone.borrow()));
-        label.refresh();
+        label
+            .set_content(&format!("{:>3}", *count_clone.borrow()))
+            .expect("Failed to update label");
+        label.refresh().expect("Failed to refresh label");
     });
     let minus = Button::new(&buttons, 0, "-").expect("Failed to create minus button");
-    minus.invoke(minus_callback);
+    minus
+        .invoke(minus_callback)
+        .expect("Failed to set minus callback");
 
     // Create and store plus button callback
     let plus_callback = Box::new(move || {
         *count.borrow_mut() += 1;
         let mut label = label.borrow_mut();
         // Format the number into a string with proper width
-        label.set_content(&format!("{:>3}", *count.borrow()));
-        label.refresh();
+        label
+            .set_content(&format!("{:>3}", *count.borrow()))
+            .expect("Failed to update label");
+        label.refresh().expect("Failed to refresh label");
     });
     let plus = Button::new(&buttons, 0, "+").expect("Failed to create plus button");
-    plus.invoke(plus_callback);
+    plus.invoke(plus_callback)
+        .expect("Failed to set plus callback");
 
     // Start the message loop
     ui::message_loop();
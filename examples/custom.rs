@@ -0,0 +1,60 @@
+use luigi_rs::{
+    self as ui, Color, Custom, CustomElement, ElementContext, Mouse, Painter, Panel, Rect,
+    Response, Window,
+};
+
+// A swatch that cycles through hues when clicked
+struct Swatch {
+    hue: f32,
+}
+
+impl CustomElement for Swatch {
+    const CLASS_NAME: &'static str = "Swatch";
+
+    fn paint(&mut self, cx: &mut ElementContext, painter: &mut Painter) {
+        let bounds = cx.bounds();
+        painter.rectangle(
+            bounds,
+            Color::from_hsv(self.hue, 0.6, 0.9),
+            Color::BLACK,
+            Rect::new(1, 1, 1, 1),
+        );
+        painter.string(bounds, "Click me", Color::BLACK, ui::UI_ALIGN_CENTER, None);
+    }
+
+    fn get_width(&mut self, _cx: &mut ElementContext, _height: i32) -> i32 {
+        160
+    }
+
+    fn get_height(&mut self, _cx: &mut ElementContext, _width: i32) -> i32 {
+        80
+    }
+
+    fn mouse(&mut self, cx: &mut ElementContext, event: Mouse) -> Response {
+        if event != Mouse::LeftDown {
+            return Response::Unhandled;
+        }
+        self.hue = (self.hue + 0.1) % 1.0;
+        cx.repaint();
+        Response::Handled
+    }
+}
+
+fn main() {
+    // Initialize the UI system
+    ui::init();
+
+    // Create main window
+    let window = Window::new("Custom Element", 300, 200, 0).expect("Failed to create window");
+
+    // Create a panel to hold the custom element
+    let panel = Panel::new(&window, ui::UI_PANEL_GRAY | ui::UI_PANEL_MEDIUM_SPACING)
+        .expect("Failed to create panel");
+
+    // Create the custom element
+    let _swatch =
+        Custom::new(&panel, 0, Swatch { hue: 0.0 }).expect("Failed to create custom element");
+
+    // Start the message loop
+    ui::message_loop();
+}
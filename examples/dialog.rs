@@ -0,0 +1,43 @@
+use luigi_rs::{self as ui, Button, Dialog, Element, Label, Panel, Widget, Window};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn main() {
+    ui::init();
+
+    let window = Window::new("Dialog", 300, 150, 0).expect("Failed to create window");
+    let panel = Panel::new(&window, ui::UI_PANEL_GRAY | ui::UI_PANEL_MEDIUM_SPACING)
+        .expect("Failed to create panel");
+    let label = Label::new(&panel, 0, "Name: untitled").expect("Failed to create label");
+    let button = Button::new(&panel, 0, "Rename...").expect("Failed to create button");
+
+    // The dialog needs the window, so the callback upgrades a handle to it
+    let handle = window.handle();
+    let name = Rc::new(RefCell::new(String::from("untitled")));
+    let label = Rc::new(RefCell::new(label));
+    button
+        .invoke(Box::new(move || {
+            let window = handle.upgrade().expect("Window is gone");
+            let mut name = name.borrow_mut();
+            let result = Dialog::new()
+                .label("New name:")
+                .textbox(&mut name)
+                .separator()
+                .fill()
+                .default_button("Rename")
+                .cancel_button("Cancel")
+                .show(&window)
+                .expect("Failed to show dialog");
+
+            if result.pressed("Rename") {
+                let mut label = label.borrow_mut();
+                label
+                    .set_content(&format!("Name: {name}"))
+                    .expect("Failed to update label");
+                label.refresh().expect("Failed to refresh label");
+            }
+        }))
+        .expect("Failed to set callback");
+
+    ui::message_loop();
+}
@@ -0,0 +1,36 @@
+use luigi_rs::{self as ui, Button, MouseButton, Panel, TextBox, Window};
+use std::cell::Cell;
+use std::rc::Rc;
+
+// Run with `cargo run --example headless --features headless`, no display needed
+fn main() {
+    ui::init();
+
+    let window = Window::new("Headless", 300, 100, 0).expect("Failed to create window");
+    let panel = Panel::new(&window, ui::UI_PANEL_GRAY | ui::UI_PANEL_MEDIUM_SPACING)
+        .expect("Failed to create panel");
+    let button =
+        Button::new(&panel, ui::UI_ELEMENT_H_FILL, "Submit").expect("Failed to create button");
+    let textbox = TextBox::new(&panel, ui::UI_ELEMENT_H_FILL).expect("Failed to create textbox");
+
+    let clicks = Rc::new(Cell::new(0));
+    let counter = clicks.clone();
+    button
+        .invoke(Box::new(move || counter.set(counter.get() + 1)))
+        .expect("Failed to set callback");
+
+    // Input goes through the same path as real events
+    window
+        .click(150, 15, MouseButton::Left)
+        .expect("Failed to click");
+    window
+        .click(150, 50, MouseButton::Left)
+        .expect("Failed to click");
+    window.type_text("Hello, headless").expect("Failed to type");
+
+    println!("Button clicked {} time(s)", clicks.get());
+    println!(
+        "Textbox contains {:?}",
+        textbox.get_text().expect("Failed to get text")
+    );
+}
@@ -0,0 +1,36 @@
+use luigi_rs::{self as ui, App, Button, CloseAction, Label, Panel, Widget, Window};
+
+fn main() {
+    let app = App::new();
+
+    let main = Window::new("Main", 400, 300, 0).expect("Failed to create window");
+    let panel = Panel::new(&main, ui::UI_PANEL_GRAY | ui::UI_PANEL_MEDIUM_SPACING)
+        .expect("Failed to create panel");
+    let button = Button::new(&panel, 0, "Open tool window").expect("Failed to create button");
+
+    // Tool windows are owned by the main window, so they close along with it
+    let handle = main.handle();
+    button
+        .invoke(Box::new(move || {
+            let main = handle.upgrade().expect("Main window is gone");
+            let tool = Window::builder()
+                .title("Tool")
+                .size(200, 100)
+                .flags(ui::UI_WINDOW_CENTER_IN_OWNER)
+                .owner(&main)
+                .build()
+                .expect("Failed to create tool window");
+            let panel = Panel::new(&tool, ui::UI_PANEL_GRAY).expect("Failed to create panel");
+            Label::new(&panel, 0, "Close me freely").expect("Failed to create label");
+        }))
+        .expect("Failed to set callback");
+
+    // Closing the main window also destroys its tool windows, which ends the application
+    main.on_close(|| {
+        println!("Main window closing");
+        CloseAction::Close
+    })
+    .expect("Failed to set close handler");
+
+    app.run();
+}
@@ -0,0 +1,35 @@
+use luigi_rs::{self as ui, Element, Label, Panel, Widget, Window};
+use std::thread;
+use std::time::Duration;
+
+fn main() {
+    ui::init();
+
+    let window = Window::new("Worker", 300, 100, 0).expect("Failed to create window");
+    let panel = Panel::new(&window, ui::UI_PANEL_GRAY | ui::UI_PANEL_MEDIUM_SPACING)
+        .expect("Failed to create panel");
+    let label = Label::new(&panel, 0, "Working...").expect("Failed to create label");
+
+    // Handles can cross threads, the label itself cannot
+    let label = label.handle();
+    let sender = window.sender().expect("Failed to create sender");
+
+    thread::spawn(move || {
+        for step in 1..=10 {
+            thread::sleep(Duration::from_millis(500));
+            let text = format!("Step {step} of 10");
+            let sent = sender.send(move || {
+                if let Ok(mut label) = label.upgrade() {
+                    label.set_content(&text).expect("Failed to update label");
+                    label.refresh().expect("Failed to refresh label");
+                }
+            });
+            // Stop once the window is closed
+            if sent.is_err() {
+                break;
+            }
+        }
+    });
+
+    ui::message_loop();
+}
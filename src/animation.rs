@@ -0,0 +1,259 @@
+//! Per-frame callbacks and tweens on top of `UIElementAnimate`.
+//!
+//! Luigi only animates one element at a time, so the first animated element drives
+//! every Rust animation from its `UI_MSG_ANIMATE` and hands over when it stops.
+
+use crate::{registry, sys, Element, Error, Result};
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::f32::consts::PI;
+use std::ptr;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+type Callback = Rc<RefCell<Box<dyn FnMut() -> bool>>>;
+
+struct Entry {
+    id: u64,
+    element: *mut sys::UIElement,
+    element_id: u32,
+    callback: Callback,
+}
+
+thread_local! {
+    static ANIMATIONS: RefCell<Vec<Entry>> = const { RefCell::new(Vec::new()) };
+    static DRIVER: Cell<Option<(*mut sys::UIElement, u32)>> = const { Cell::new(None) };
+    static HOOKED: RefCell<HashSet<u32>> = RefCell::new(HashSet::new());
+    static NEXT_ANIMATION: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A running animation, returned by [`Element::animate`] and the tween helpers
+///
+/// Dropping the handle does not stop the animation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Animation {
+    id: u64,
+}
+
+impl Animation {
+    /// Stop the animation; its callback is dropped and will not run again
+    pub fn stop(self) {
+        let removed = ANIMATIONS.with(|animations| {
+            let mut animations = animations.borrow_mut();
+            let index = animations.iter().position(|entry| entry.id == self.id)?;
+            Some(animations.remove(index))
+        });
+        if removed.is_some() {
+            update_driver();
+        }
+    }
+
+    /// Check whether the animation is still running
+    pub fn is_running(&self) -> bool {
+        ANIMATIONS.with(|animations| animations.borrow().iter().any(|entry| entry.id == self.id))
+    }
+}
+
+/// # Safety
+/// `element` must be a live element with the given id.
+pub(crate) unsafe fn start(
+    element: *mut sys::UIElement,
+    element_id: u32,
+    callback: Box<dyn FnMut() -> bool>,
+) -> Result<Animation> {
+    if HOOKED.with(|hooked| hooked.borrow_mut().insert(element_id)) {
+        let tick = move || {
+            if DRIVER
+                .with(Cell::get)
+                .is_some_and(|(_, id)| id == element_id)
+            {
+                tick();
+            }
+        };
+        registry::listen(element, sys::UIMessage_UI_MSG_ANIMATE, Box::new(tick));
+        let destroyed = move || forget_element(element_id);
+        registry::listen(element, sys::UIMessage_UI_MSG_DESTROY, Box::new(destroyed));
+    }
+
+    let id = NEXT_ANIMATION.with(|next| next.replace(next.get() + 1));
+    ANIMATIONS.with(|animations| {
+        animations.borrow_mut().push(Entry {
+            id,
+            element,
+            element_id,
+            callback: Rc::new(RefCell::new(callback)),
+        })
+    });
+    if !update_driver() {
+        Animation { id }.stop();
+        return Err(Error::AnimationBusy);
+    }
+    Ok(Animation { id })
+}
+
+// Make sure exactly one live element with animations is driving them.
+// Returns false if Luigi is busy animating an element of its own, like a scroll button.
+fn update_driver() -> bool {
+    let driver = DRIVER.with(Cell::get);
+    let next = ANIMATIONS.with(|animations| {
+        let animations = animations.borrow();
+        let alive = |&(element, id): &(*mut sys::UIElement, u32)| {
+            registry::is_alive(element, id) && animations.iter().any(|entry| entry.element_id == id)
+        };
+        driver.filter(alive).or_else(|| {
+            animations
+                .iter()
+                .map(|entry| (entry.element, entry.element_id))
+                .find(alive)
+        })
+    });
+    if next == driver {
+        return true;
+    }
+
+    if let Some((element, _)) = driver {
+        unsafe { sys::UIElementAnimate(element, true) };
+    }
+    DRIVER.with(|d| d.set(None));
+    if let Some((element, id)) = next {
+        if !unsafe { sys::UIElementAnimate(element, false) } {
+            return false;
+        }
+        DRIVER.with(|d| d.set(Some((element, id))));
+    }
+    true
+}
+
+fn tick() {
+    let callbacks = ANIMATIONS.with(|animations| {
+        animations
+            .borrow()
+            .iter()
+            .filter(|entry| registry::is_alive(entry.element, entry.element_id))
+            .map(|entry| (entry.id, entry.callback.clone()))
+            .collect::<Vec<_>>()
+    });
+
+    let mut finished = Vec::new();
+    for (id, callback) in callbacks {
+        let running = match callback.try_borrow_mut() {
+            Ok(mut callback) => callback(),
+            Err(_) => true,
+        };
+        if !running {
+            finished.push(id);
+        }
+    }
+
+    let removed = ANIMATIONS.with(|animations| {
+        let mut animations = animations.borrow_mut();
+        let (removed, kept) = std::mem::take(&mut *animations)
+            .into_iter()
+            .partition::<Vec<_>, _>(|entry| finished.contains(&entry.id));
+        *animations = kept;
+        removed
+    });
+    // Dropped outside the borrow, since callbacks may own elements.
+    drop(removed);
+    update_driver();
+}
+
+fn forget_element(element_id: u32) {
+    HOOKED.with(|hooked| hooked.borrow_mut().remove(&element_id));
+    let removed = ANIMATIONS.with(|animations| {
+        let mut animations = animations.borrow_mut();
+        let (removed, kept) = std::mem::take(&mut *animations)
+            .into_iter()
+            .partition::<Vec<_>, _>(|entry| entry.element_id == element_id);
+        *animations = kept;
+        removed
+    });
+    drop(removed);
+    update_driver();
+}
+
+/// Easing curves mapping linear progress (0.0-1.0) to eased progress
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Easing {
+    Linear,
+    /// Start slowly and speed up
+    EaseIn,
+    /// Start quickly and slow down
+    EaseOut,
+    /// Speed up then slow down
+    #[default]
+    EaseInOut,
+    /// Sine based ease in and out, gentler than [`Easing::EaseInOut`]
+    Sine,
+    /// Overshoot the target slightly before settling
+    Back,
+    /// Any other curve
+    Custom(fn(f32) -> f32),
+}
+
+impl Easing {
+    /// Map linear progress to eased progress; `t` is clamped to 0.0-1.0
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            Easing::Sine => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::Back => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+            Easing::Custom(curve) => curve(t),
+        }
+    }
+}
+
+/// Call `apply` with the eased progress (0.0-1.0) every frame for `duration`
+///
+/// The last call is always made with exactly 1.0, after which the animation stops.
+pub fn tween(
+    element: &impl Element,
+    duration: Duration,
+    easing: Easing,
+    mut apply: impl FnMut(f32) + 'static,
+) -> Result<Animation> {
+    let raw = element.live_element()?;
+    // Not `animate_clock`, which counts CPU time on Linux and stalls while the loop waits.
+    let started = Instant::now();
+    let duration = duration.as_secs_f32().max(0.001);
+    let callback = move || {
+        let t = (started.elapsed().as_secs_f32() / duration).min(1.0);
+        apply(if t < 1.0 { easing.apply(t) } else { 1.0 });
+        t < 1.0
+    };
+    unsafe { start(raw, element.id(), Box::new(callback)) }
+}
+
+/// Tween a field of `element`, such as a slider's position, repainting it every frame
+///
+/// # Safety
+/// `field` must point into `element`, which must be live.
+pub(crate) unsafe fn tween_field(
+    element: &impl Element,
+    field: *mut f32,
+    to: f32,
+    duration: Duration,
+    easing: Easing,
+) -> Result<Animation> {
+    let raw = element.raw_element();
+    let from = *field;
+    // The animation ends when the element is destroyed, so `field` outlives it.
+    tween(element, duration, easing, move |t| {
+        *field = lerp(from, to, t);
+        sys::UIElementRepaint(raw, ptr::null_mut());
+    })
+}
+
+/// Interpolate between `from` and `to` by `t`
+pub fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
@@ -0,0 +1,154 @@
+//! Window lifetimes: closing, owned windows and the application object.
+//!
+//! Luigi ends the message loop as soon as any window is closed. Every window created
+//! by the crate gets a close hook that instead destroys the window, and only lets the
+//! loop end once it was the last one.
+
+use crate::{init, message_loop, registry, sys, CloseAction, Widget, Window};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+type CloseHandler = Rc<RefCell<Box<dyn FnMut() -> CloseAction>>>;
+
+thread_local! {
+    static CLOSE_HANDLERS: RefCell<HashMap<u32, CloseHandler>> = RefCell::new(HashMap::new());
+}
+
+/// Hook a newly created window's close and destroy messages
+///
+/// With the `debug` feature, also start counting its fills for [`Window::last_overdraw`].
+///
+/// # Safety
+/// `window` and `owner` must be live windows.
+pub(crate) unsafe fn manage(window: *mut sys::UIWindow, owner: Option<*mut sys::UIWindow>) {
+    let id = (*window).e.id;
+    #[cfg(feature = "debug")]
+    sys::LuigiRsWindowCountFills(window);
+    registry::intercept(
+        window.cast(),
+        sys::UIMessage_UI_MSG_WINDOW_CLOSE,
+        Box::new(move |_, _| close(window, id)),
+    );
+    registry::listen(
+        window.cast(),
+        sys::UIMessage_UI_MSG_DESTROY,
+        Box::new(move || {
+            let handler = CLOSE_HANDLERS.with(|handlers| handlers.borrow_mut().remove(&id));
+            drop(handler);
+            if open_windows().is_empty() {
+                sys::LuigiRsQuit();
+            }
+        }),
+    );
+
+    if let Some(owner) = owner {
+        // Owned windows go away with their owner.
+        registry::listen(
+            owner.cast(),
+            sys::UIMessage_UI_MSG_DESTROY,
+            Box::new(move || {
+                if registry::is_alive(window.cast(), id) {
+                    sys::UIElementDestroy(window.cast());
+                }
+            }),
+        );
+    }
+}
+
+/// Set the callback asked before a window closes, replacing any previous one
+pub(crate) fn set_close_handler(window: u32, callback: Box<dyn FnMut() -> CloseAction>) {
+    let old = CLOSE_HANDLERS.with(|handlers| {
+        handlers
+            .borrow_mut()
+            .insert(window, Rc::new(RefCell::new(callback)))
+    });
+    drop(old);
+}
+
+unsafe fn close(window: *mut sys::UIWindow, id: u32) -> i32 {
+    let handler = CLOSE_HANDLERS.with(|handlers| handlers.borrow().get(&id).cloned());
+    if let Some(handler) = handler {
+        // Asked again while the handler is still deciding, e.g. from its own dialog.
+        let Ok(mut handler) = handler.try_borrow_mut() else {
+            return 1;
+        };
+        if handler() == CloseAction::Cancel {
+            return 1;
+        }
+    }
+
+    if open_windows().iter().any(|&other| other != window) {
+        sys::UIElementDestroy(window.cast());
+        1
+    } else {
+        // The last window: let Luigi end the message loop.
+        0
+    }
+}
+
+/// Top-level windows that are not being destroyed, newest first
+fn open_windows() -> Vec<*mut sys::UIWindow> {
+    let hidden = sys::UI_WINDOW_MENU | sys::UI_WINDOW_INSPECTOR | sys::UI_ELEMENT_DESTROY;
+    let mut windows = Vec::new();
+    let mut window = unsafe { sys::LuigiRsWindows() };
+    while !window.is_null() {
+        unsafe {
+            if (*window).e.flags & hidden == 0 {
+                windows.push(window);
+            }
+            window = (*window).next;
+        }
+    }
+    windows
+}
+
+/// An application made of one or more windows
+///
+/// Closing a window destroys it, and [`App::run`] returns once the last window is
+/// closed or destroyed. Windows are created as usual with [`Window::new`] or
+/// [`Window::builder`].
+pub struct App {
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl App {
+    /// Initialize Luigi, see [`init`]
+    pub fn new() -> Self {
+        init();
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Get the open windows, newest first
+    ///
+    /// Menus, the inspector and other windows that Luigi creates itself are not
+    /// included.
+    pub fn windows(&self) -> Vec<Window> {
+        open_windows()
+            .into_iter()
+            .filter_map(|window| unsafe {
+                let id = (*window).e.id;
+                registry::is_alive(window.cast(), id).then(|| Window::from_raw(window.cast(), id))
+            })
+            .collect()
+    }
+
+    /// Run the message loop until the last window closes, see [`message_loop`]
+    pub fn run(self) -> i32 {
+        message_loop()
+    }
+
+    /// End [`App::run`] after the current message, leaving the windows open
+    pub fn quit(&self) {
+        unsafe { sys::LuigiRsQuit() };
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
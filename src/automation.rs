@@ -0,0 +1,144 @@
+//! Luigi's automation harness, for driving the UI from tests.
+//!
+//! With the `automation` feature, [`crate::message_loop`] runs the callback given to
+//! [`Automation::set_tests`] instead of waiting for events. Keyboard input goes to the
+//! most recently created window, as in Luigi's own harness.
+
+use crate::{registry, sys, unwind, Code, Element, Error, Key, Result, Table};
+use std::cell::RefCell;
+use std::ffi::{c_int, CString};
+use std::marker::PhantomData;
+
+type Tests = Box<dyn FnOnce(&mut Automation) -> i32>;
+
+thread_local! {
+    static TESTS: RefCell<Option<Tests>> = RefCell::new(None);
+}
+
+/// Drives the UI from inside [`crate::message_loop`], see [`Automation::set_tests`]
+pub struct Automation {
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl Automation {
+    /// Run `tests` in place of the event loop the next time [`crate::message_loop`] is
+    /// called, replacing Luigi's `UIAutomationRunTests`
+    ///
+    /// The loop returns what `tests` returns. A panic inside `tests`, such as a failed
+    /// assertion, is resumed from [`crate::message_loop`]. Later calls to the loop
+    /// handle events as usual.
+    pub fn set_tests(tests: impl FnOnce(&mut Automation) -> i32 + 'static) {
+        TESTS.with(|slot| *slot.borrow_mut() = Some(Box::new(tests)));
+        unsafe { sys::LuigiRsAutomationSetTests(Some(run_tests)) };
+    }
+
+    /// Wait for the next event and handle it
+    pub fn process_message(&mut self) {
+        unsafe { sys::UIAutomationProcessMessage() };
+        unwind::resume();
+    }
+
+    /// Type `text` into the focused element, one key per character
+    ///
+    /// Uppercase letters are typed with shift held. Luigi's harness only handles
+    /// single-byte characters, so the text should be ASCII.
+    pub fn type_text(&mut self, text: &str) -> Result<()> {
+        let text = CString::new(text).map_err(|_| Error::InvalidString)?;
+        self.check_window()?;
+        unsafe { sys::UIAutomationKeyboardType(text.as_ptr()) };
+        unwind::resume();
+        Ok(())
+    }
+
+    /// Press `key` with the given modifiers held, then release the modifiers
+    pub fn press_shortcut(&mut self, key: Key, ctrl: bool, shift: bool, alt: bool) -> Result<()> {
+        self.check_window()?;
+        let code = key.code() as isize;
+        unsafe { sys::UIAutomationKeyboardTypeSingle(code, ctrl, shift, alt) };
+        unwind::resume();
+        Ok(())
+    }
+
+    /// Click the centre of `element` with the left mouse button
+    ///
+    /// The click is synthesized as a mouse move followed by `UI_MSG_LEFT_DOWN` and
+    /// `UI_MSG_LEFT_UP`, so it reaches whatever is on top at that point.
+    ///
+    /// # Errors
+    /// [`Error::NotVisible`] if the element is hidden or clipped away entirely.
+    pub fn click(&mut self, element: &impl Element) -> Result<()> {
+        let clicked = unsafe { sys::LuigiRsAutomationClick(element.live_element()?) };
+        unwind::resume();
+        if !clicked {
+            return Err(Error::NotVisible);
+        }
+        Ok(())
+    }
+
+    /// Check whether line `line` of `code`, counting from 1, is exactly `text`
+    pub fn code_line_matches(&self, code: &Code, line: i32, text: &str) -> Result<bool> {
+        let raw = registry::live(code.raw, code.id)?;
+        let text = CString::new(text).map_err(|_| Error::InvalidString)?;
+        Ok(unsafe { sys::UIAutomationCheckCodeLineMatches(raw, line, text.as_ptr()) })
+    }
+
+    /// Check whether the item at `row` and `column` of `table` is exactly `text`
+    pub fn table_item_matches(
+        &self,
+        table: &Table,
+        row: i32,
+        column: i32,
+        text: &str,
+    ) -> Result<bool> {
+        let raw = registry::live(table.raw, table.id)?;
+        let text = CString::new(text).map_err(|_| Error::InvalidString)?;
+        Ok(unsafe { sys::UIAutomationCheckTableItemMatches(raw, row, column, text.as_ptr()) })
+    }
+
+    /// Assert that line `line` of `code`, counting from 1, is exactly `text`
+    ///
+    /// # Panics
+    /// If the line differs, or `code` has been destroyed.
+    #[track_caller]
+    pub fn assert_code_line(&self, code: &Code, line: i32, text: &str) {
+        let matches = self.code_line_matches(code, line, text);
+        assert!(
+            matches!(matches, Ok(true)),
+            "code line {line} is not {text:?} ({matches:?})"
+        );
+    }
+
+    /// Assert that the item at `row` and `column` of `table` is exactly `text`
+    ///
+    /// # Panics
+    /// If the item differs, or `table` has been destroyed.
+    #[track_caller]
+    pub fn assert_table_item(&self, table: &Table, row: i32, column: i32, text: &str) {
+        let matches = self.table_item_matches(table, row, column, text);
+        assert!(
+            matches!(matches, Ok(true)),
+            "table item {row}, {column} is not {text:?} ({matches:?})"
+        );
+    }
+
+    /// Luigi's harness sends keys to the newest window, which must exist
+    fn check_window(&self) -> Result<()> {
+        if unsafe { sys::LuigiRsWindows() }.is_null() {
+            return Err(Error::NullPointer);
+        }
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn run_tests() -> c_int {
+    // The tests run once, later loops handle events.
+    sys::LuigiRsAutomationSetTests(None);
+    let Some(tests) = TESTS.with(|slot| slot.borrow_mut().take()) else {
+        return 0;
+    };
+    unwind::catch(1, || {
+        tests(&mut Automation {
+            _not_send: PhantomData,
+        })
+    })
+}
@@ -0,0 +1,287 @@
+//! Elements implemented in Rust on top of `UIElementCreate`.
+
+use crate::{
+    registry, sys, tween, unwind, Animation, Easing, Element, Error, KeyTyped, Message, Painter,
+    Rect, Response, Result, Widget,
+};
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_void, CString};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+use std::time::Duration;
+
+/// Mouse input delivered to [`CustomElement::mouse`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mouse {
+    LeftDown,
+    LeftUp,
+    MiddleDown,
+    MiddleUp,
+    RightDown,
+    RightUp,
+    Move,
+    /// The mouse moved while a button was held on the element
+    Drag,
+    /// The wheel moved by the given delta
+    Wheel(i32),
+}
+
+/// Behaviour of an element implemented in Rust
+///
+/// Every method has a default that does nothing, so implementations only override
+/// what they need. The element owns the value and drops it when it is destroyed.
+pub trait CustomElement: 'static {
+    /// Class name shown in the inspector
+    const CLASS_NAME: &'static str;
+
+    /// Paint the element; the painter is clipped to the element
+    fn paint(&mut self, cx: &mut ElementContext, painter: &mut Painter) {
+        let _ = (cx, painter);
+    }
+
+    /// Lay out child elements within [`ElementContext::bounds`]
+    fn layout(&mut self, cx: &mut ElementContext) {
+        let _ = cx;
+    }
+
+    /// Preferred width, given the height if known
+    fn get_width(&mut self, cx: &mut ElementContext, height: i32) -> i32 {
+        let _ = (cx, height);
+        0
+    }
+
+    /// Preferred height, given the width if known
+    fn get_height(&mut self, cx: &mut ElementContext, width: i32) -> i32 {
+        let _ = (cx, width);
+        0
+    }
+
+    /// Handle mouse input; the cursor position is [`ElementContext::cursor`]
+    fn mouse(&mut self, cx: &mut ElementContext, event: Mouse) -> Response {
+        let _ = (cx, event);
+        Response::Unhandled
+    }
+
+    /// Handle a key typed while the element has focus
+    fn key(&mut self, cx: &mut ElementContext, key: KeyTyped) -> Response {
+        let _ = (cx, key);
+        Response::Unhandled
+    }
+
+    /// Handle any other message
+    fn message(&mut self, cx: &mut ElementContext, message: Message) -> Response {
+        let _ = (cx, message);
+        Response::Unhandled
+    }
+}
+
+/// The element a [`CustomElement`] method is running for
+pub struct ElementContext {
+    raw: *mut sys::UIElement,
+    id: u32,
+}
+
+impl ElementContext {
+    /// Bounds of the element in window coordinates
+    pub fn bounds(&self) -> Rect {
+        unsafe { (*self.raw).bounds.into() }
+    }
+
+    /// Cursor position in window coordinates
+    pub fn cursor(&self) -> (i32, i32) {
+        unsafe {
+            let window = (*self.raw).window;
+            ((*window).cursorX, (*window).cursorY)
+        }
+    }
+
+    /// Schedule the element to be painted again
+    pub fn repaint(&mut self) {
+        unsafe { sys::UIElementRepaint(self.raw, ptr::null_mut()) }
+    }
+}
+
+impl Element for ElementContext {
+    fn raw_element(&self) -> *mut sys::UIElement {
+        self.raw
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+// Memory layout of a custom element, allocated by `UIElementCreate`
+#[repr(C)]
+struct RawCustom {
+    e: sys::UIElement,
+    state: *mut c_void,
+}
+
+/// An element whose behaviour is implemented by `T`
+pub struct Custom<T> {
+    raw: *mut sys::UIElement,
+    id: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T: CustomElement> Custom<T> {
+    /// Create a new custom element owning `state`
+    ///
+    /// # Arguments
+    /// * `parent` - Parent element to attach this element to
+    /// * `flags` - Element flags; the low 16 bits are free for the element's own use
+    /// * `state` - Value implementing the element's behaviour
+    pub fn new(parent: &impl Element, flags: u32, state: T) -> Result<Self> {
+        let raw = unsafe {
+            sys::UIElementCreate(
+                mem::size_of::<RawCustom>(),
+                parent.live_element()?,
+                flags,
+                Some(class_message::<T>),
+                class_name::<T>(),
+            )
+        };
+        if raw.is_null() {
+            return Err(Error::CreateFailed);
+        }
+        let state = Box::into_raw(Box::new(RefCell::new(state)));
+        unsafe { (*(raw as *mut RawCustom)).state = state as *mut c_void };
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw) },
+            _marker: PhantomData,
+        })
+    }
+
+    /// Access the element's state
+    ///
+    /// # Panics
+    /// Panics if called from within one of the element's own [`CustomElement`] methods.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R> {
+        let raw = registry::live(self.raw, self.id)?;
+        let state = unsafe { &*((*(raw as *mut RawCustom)).state as *const RefCell<T>) };
+        let result = f(&mut state.borrow_mut());
+        Ok(result)
+    }
+
+    /// Animate a property of the state, repainting the element every frame
+    ///
+    /// `apply` is called with the eased progress (0.0-1.0); use [`crate::lerp`] to
+    /// interpolate the property from its starting value.
+    pub fn tween(
+        &self,
+        duration: Duration,
+        easing: Easing,
+        mut apply: impl FnMut(&mut T, f32) + 'static,
+    ) -> Result<Animation> {
+        let raw = registry::live(self.raw, self.id)?;
+        // The animation ends when the element is destroyed, so the state outlives it.
+        let state = unsafe { (*(raw as *mut RawCustom)).state as *const RefCell<T> };
+        tween(self, duration, easing, move |t| unsafe {
+            if let Ok(mut state) = (*state).try_borrow_mut() {
+                apply(&mut state, t);
+            }
+            sys::UIElementRepaint(raw, ptr::null_mut());
+        })
+    }
+}
+
+impl<T> Element for Custom<T> {
+    fn raw_element(&self) -> *mut sys::UIElement {
+        self.raw
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl<T: CustomElement> Widget for Custom<T> {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw,
+            id,
+            _marker: PhantomData,
+        }
+    }
+}
+
+thread_local! {
+    static CLASS_NAMES: RefCell<HashMap<TypeId, CString>> = RefCell::new(HashMap::new());
+}
+
+// Luigi keeps the class name pointer, so each name is interned for the rest of the program.
+fn class_name<T: CustomElement>() -> *const std::os::raw::c_char {
+    CLASS_NAMES.with(|names| {
+        names
+            .borrow_mut()
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| CString::new(T::CLASS_NAME).unwrap_or_default())
+            .as_ptr()
+    })
+}
+
+unsafe extern "C" fn class_message<T: CustomElement>(
+    element: *mut sys::UIElement,
+    message: sys::UIMessage,
+    di: i32,
+    dp: *mut c_void,
+) -> i32 {
+    let raw = element as *mut RawCustom;
+    let state = (*raw).state as *mut RefCell<T>;
+    if state.is_null() {
+        return 0;
+    }
+
+    // Messages sent while a method is already running for this element get the defaults.
+    let result = match (*state).try_borrow_mut() {
+        Ok(mut state) => {
+            let mut cx = ElementContext {
+                raw: element,
+                id: (*element).id,
+            };
+            unwind::catch(0, || {
+                let message = Message::decode(message, di, dp);
+                dispatch(&mut *state, &mut cx, message)
+            })
+        }
+        Err(_) => 0,
+    };
+
+    if message == sys::UIMessage_UI_MSG_DESTROY {
+        (*raw).state = ptr::null_mut();
+        unwind::catch((), || drop(Box::from_raw(state)));
+    }
+    result
+}
+
+fn dispatch<T: CustomElement>(state: &mut T, cx: &mut ElementContext, message: Message) -> i32 {
+    let mouse = |state: &mut T, cx: &mut ElementContext, event| state.mouse(cx, event).into_raw();
+    match message {
+        Message::Paint(mut painter) => {
+            state.paint(cx, &mut painter);
+            0
+        }
+        Message::Layout => {
+            state.layout(cx);
+            0
+        }
+        Message::GetWidth(height) => state.get_width(cx, height),
+        Message::GetHeight(width) => state.get_height(cx, width),
+        Message::LeftDown => mouse(state, cx, Mouse::LeftDown),
+        Message::LeftUp => mouse(state, cx, Mouse::LeftUp),
+        Message::MiddleDown => mouse(state, cx, Mouse::MiddleDown),
+        Message::MiddleUp => mouse(state, cx, Mouse::MiddleUp),
+        Message::RightDown => mouse(state, cx, Mouse::RightDown),
+        Message::RightUp => mouse(state, cx, Mouse::RightUp),
+        Message::MouseMove => mouse(state, cx, Mouse::Move),
+        Message::MouseDrag => mouse(state, cx, Mouse::Drag),
+        Message::MouseWheel(delta) => mouse(state, cx, Mouse::Wheel(delta)),
+        Message::KeyTyped(key) => state.key(cx, key).into_raw(),
+        message => state.message(cx, message).into_raw(),
+    }
+}
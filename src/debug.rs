@@ -0,0 +1,142 @@
+//! Luigi's inspector window and paint statistics, built with `UI_DEBUG`.
+
+use crate::{registry, sys, unwind, Error, Result, UiSender, Window};
+use std::ffi::CString;
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+
+type Fallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Sends records from the `log` crate to the log pane of Luigi's inspector window
+///
+/// Records logged on the UI thread before the message loop has created the
+/// inspector, or on other threads, are delivered through the window's
+/// [`UiSender`]. If that window is gone, they are passed to the fallback set with
+/// [`InspectorLogger::with_fallback`], or dropped.
+pub struct InspectorLogger {
+    sender: UiSender,
+    ui_thread: ThreadId,
+    fallback: Option<Fallback>,
+}
+
+impl InspectorLogger {
+    /// Create a logger that delivers records through `window`
+    ///
+    /// Must be called on the UI thread.
+    pub fn new(window: &Window) -> Result<Self> {
+        Ok(Self {
+            sender: window.sender()?,
+            ui_thread: thread::current().id(),
+            fallback: None,
+        })
+    }
+
+    /// Pass formatted lines that cannot reach the inspector to `fallback`
+    ///
+    /// It may be called on any thread.
+    pub fn with_fallback(mut self, fallback: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.fallback = Some(Arc::new(fallback));
+        self
+    }
+
+    /// Install as the global logger, letting through records up to `level`
+    ///
+    /// # Errors
+    /// [`Error::LoggerInstalled`] if a global logger has already been set.
+    pub fn install(self, level: log::LevelFilter) -> Result<()> {
+        log::set_logger(Box::leak(Box::new(self))).map_err(|_| Error::LoggerInstalled)?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl log::Log for InspectorLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let line = format!("[{} {}] {}", record.level(), record.target(), record.args());
+        if thread::current().id() == self.ui_thread && write_line(&line) {
+            return;
+        }
+
+        let deferred = line.clone();
+        let fallback = self.fallback.clone();
+        let sent = self.sender.send(move || {
+            if !write_line(&deferred) {
+                if let Some(fallback) = fallback {
+                    fallback(&deferred);
+                }
+            }
+        });
+        if let (Err(_), Some(fallback)) = (sent, &self.fallback) {
+            fallback(&line);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Add a line to the inspector's log pane, if the inspector exists yet
+fn write_line(line: &str) -> bool {
+    // Interior NULs would cut the line short, so they are dropped.
+    let line = CString::new(line.replace('\0', "")).unwrap_or_default();
+    unsafe { sys::LuigiRsInspectorLog(line.as_ptr()) }
+}
+
+/// How much painting a paint pass of a window did, from [`Window::overdraw`] or
+/// [`Window::last_overdraw`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overdraw {
+    /// Pixels filled while painting, counting a pixel each time it is filled
+    pub fill_count: u64,
+    /// Pixels in the repainted part of the window
+    pub area: u64,
+}
+
+impl Overdraw {
+    /// Get the average number of times each pixel was filled
+    ///
+    /// A window whose elements each paint their own background scores about 1.
+    /// Values well above that mean elements paint over each other.
+    pub fn ratio(&self) -> f32 {
+        if self.area == 0 {
+            0.0
+        } else {
+            self.fill_count as f32 / self.area as f32
+        }
+    }
+}
+
+impl Window {
+    /// Repaint the whole window now and count the pixels filled, to diagnose overdraw
+    ///
+    /// This costs a full repaint of the window on every call. To watch the repaints
+    /// that happen anyway, use [`Window::last_overdraw`] instead.
+    ///
+    /// Fails with [`Error::NullPointer`] if the window has no framebuffer yet.
+    pub fn overdraw(&self) -> Result<Overdraw> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe { sys::LuigiRsWindowCapture(window) };
+        unwind::resume();
+        self.last_overdraw()?.ok_or(Error::NullPointer)
+    }
+
+    /// Count the pixels filled by the most recent paint pass of the window
+    ///
+    /// This is the statistic behind Luigi's `lastFullFillCount`, which Luigi itself
+    /// computes wrongly. Passes only repaint the changed part of the window, so
+    /// [`Overdraw::area`] varies. Returns `None` if the window has not painted yet.
+    pub fn last_overdraw(&self) -> Result<Option<Overdraw>> {
+        let window = registry::live(self.raw, self.id)?;
+        let (mut fill_count, mut area) = (0, 0);
+        if !unsafe { sys::LuigiRsWindowLastFills(window, &mut fill_count, &mut area) } {
+            return Ok(None);
+        }
+        Ok(Some(Overdraw {
+            fill_count: fill_count as u64,
+            area: area as u64,
+        }))
+    }
+}
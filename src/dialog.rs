@@ -0,0 +1,376 @@
+//! Modal dialogs built row by row, in place of the variadic `UIDialogShow`, shown with a
+//! nested message loop or awaited from a task.
+
+use crate::{
+    executor, registry, sys, unwind, Button, Element, Error, Events, Label, Message, Panel,
+    Response, Result, Spacer, TextBox, Widget, Window,
+};
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::ptr;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+enum Item<'a> {
+    Label(String),
+    Textbox(&'a mut String),
+    Button(String, Role),
+    Fill,
+    Separator,
+    Custom(*mut sys::UIElement, u32),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Normal,
+    Default,
+    Cancel,
+}
+
+/// Called with the index of the pressed button, or `None` to close without one
+type Close = Rc<dyn Fn(Option<usize>)>;
+
+/// A modal dialog, laid out as rows of labels, textboxes and buttons
+///
+/// Items are added to the current row until [`Dialog::row`] starts the next one.
+/// Enter presses the focused button, or else the default button (the first button
+/// unless one was added with [`Dialog::default_button`]). Escape presses the
+/// [`Dialog::cancel_button`], or closes the dialog without a button if there is none.
+/// As in Luigi, typing a button's first letter presses it.
+#[derive(Default)]
+pub struct Dialog<'a> {
+    rows: Vec<Vec<Item<'a>>>,
+}
+
+/// How a [`Dialog`] was closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogResult {
+    /// Label of the pressed button, or `None` if the dialog was closed with Escape
+    /// or the application quit while it was open
+    pub button: Option<String>,
+    /// Final contents of each textbox, in the order they were added
+    pub texts: Vec<String>,
+}
+
+impl DialogResult {
+    /// Check whether the button with the given label closed the dialog
+    pub fn pressed(&self, label: &str) -> bool {
+        self.button.as_deref() == Some(label)
+    }
+}
+
+/// A dialog shown with [`Dialog::show_async`], resolving once it is closed
+///
+/// The button is `None` if the dialog was closed with Escape or its window was
+/// destroyed.
+pub struct PendingDialog<'a> {
+    window: Window,
+    events: Events<Option<usize>>,
+    open: Option<Open<'a>>,
+}
+
+impl Future for PendingDialog<'_> {
+    type Output = DialogResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<DialogResult> {
+        let Poll::Ready(button) = self.events.poll_next(cx) else {
+            return Poll::Pending;
+        };
+        let open = self
+            .open
+            .take()
+            .expect("PendingDialog polled after completion");
+        Poll::Ready(open.finish(&self.window, button.flatten()))
+    }
+}
+
+impl Drop for PendingDialog<'_> {
+    fn drop(&mut self) {
+        let window = registry::live(self.window.raw, self.window.id);
+        if let (Some(_), Ok(window)) = (&self.open, window) {
+            unsafe { sys::LuigiRsDialogEnd(window) };
+        }
+    }
+}
+
+/// The parts of a shown dialog needed to build its result
+struct Open<'a> {
+    bindings: Vec<(TextBox, &'a mut String)>,
+    buttons: Vec<(Button, String, Role)>,
+}
+
+impl Open<'_> {
+    /// Close the dialog and write the textboxes back, unless it was cancelled
+    fn finish(self, window: &Window, pressed: Option<usize>) -> DialogResult {
+        let mut result = DialogResult {
+            button: None,
+            texts: Vec::new(),
+        };
+        // The window goes away with the rest of the UI if it was closed meanwhile.
+        if let Ok(raw_window) = registry::live(window.raw, window.id) {
+            result.texts = self
+                .bindings
+                .iter()
+                .map(|(textbox, _)| textbox.get_text().unwrap_or_default())
+                .collect();
+            unsafe { sys::LuigiRsDialogEnd(raw_window) };
+        }
+
+        let button = pressed.and_then(|i| self.buttons.get(i));
+        if let Some((_, label, _)) = button {
+            result.button = Some(label.clone());
+        }
+        if button.is_some_and(|(_, _, role)| *role != Role::Cancel) {
+            for ((_, target), text) in self.bindings.into_iter().zip(&result.texts) {
+                target.clone_from(text);
+            }
+        }
+        result
+    }
+}
+
+impl<'a> Dialog<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new row
+    pub fn row(mut self) -> Self {
+        self.rows.push(Vec::new());
+        self
+    }
+
+    /// Add a line of text
+    pub fn label(self, text: &str) -> Self {
+        self.push(Item::Label(text.to_string()))
+    }
+
+    /// Add a textbox filled with `text`
+    ///
+    /// The edited contents are written back when the dialog is closed with any button
+    /// other than the [`Dialog::cancel_button`].
+    pub fn textbox(self, text: &'a mut String) -> Self {
+        self.push(Item::Textbox(text))
+    }
+
+    /// Add a button that closes the dialog
+    pub fn button(self, label: &str) -> Self {
+        self.push(Item::Button(label.to_string(), Role::Normal))
+    }
+
+    /// Add a button that Enter presses when no other button is focused
+    pub fn default_button(self, label: &str) -> Self {
+        self.push(Item::Button(label.to_string(), Role::Default))
+    }
+
+    /// Add a button that Escape presses, which leaves the textbox strings untouched
+    pub fn cancel_button(self, label: &str) -> Self {
+        self.push(Item::Button(label.to_string(), Role::Cancel))
+    }
+
+    /// Add empty space that pushes the rest of the row to the right
+    pub fn fill(self) -> Self {
+        self.push(Item::Fill)
+    }
+
+    /// Add a horizontal line on a row of its own
+    pub fn separator(self) -> Self {
+        self.row().push(Item::Separator).row()
+    }
+
+    /// Move an existing element into the current row
+    ///
+    /// The element is destroyed along with the dialog.
+    pub fn custom(self, element: &impl Element) -> Self {
+        self.push(Item::Custom(element.raw_element(), element.id()))
+    }
+
+    fn push(mut self, item: Item<'a>) -> Self {
+        match self.rows.last_mut() {
+            Some(row) => row.push(item),
+            None => self.rows.push(vec![item]),
+        }
+        self
+    }
+
+    /// Show the dialog over `window` and run a nested message loop until it is closed
+    ///
+    /// Every other element of the window is disabled meanwhile.
+    ///
+    /// # Errors
+    /// [`Error::DialogOpen`] if the window is already showing a dialog.
+    pub fn show(self, window: &Window) -> Result<DialogResult> {
+        let pressed = Rc::new(Cell::new(None));
+        let slot = pressed.clone();
+        let close: Close = Rc::new(move |button| {
+            slot.set(button);
+            unsafe { sys::LuigiRsDialogClose() };
+        });
+        let (raw_window, open, focus) = self.open(window, close)?;
+
+        let closed = unsafe { sys::LuigiRsDialogRun(raw_window, focus) };
+        let result = open.finish(window, pressed.get().filter(|_| closed));
+        unwind::resume();
+        Ok(result)
+    }
+
+    /// Show the dialog over `window` and wait for it to be closed, without blocking
+    ///
+    /// Resolves to the same result as [`Dialog::show`], for futures run with
+    /// [`Window::spawn_local`]. The rest of the window stays disabled until then, and
+    /// dropping the future closes the dialog.
+    ///
+    /// # Errors
+    /// [`Error::DialogOpen`] if the window is already showing a dialog.
+    pub fn show_async(self, window: &Window) -> Result<PendingDialog<'a>> {
+        let (sink, events) = executor::events();
+        let close: Close = Rc::new(move |button| sink.push(button));
+        let (raw_window, open, focus) = self.open(window, close)?;
+        unsafe { sys::LuigiRsDialogOpen(raw_window, focus) };
+        Ok(PendingDialog {
+            window: unsafe { Window::from_raw(raw_window.cast(), window.id) },
+            events,
+            open: Some(open),
+        })
+    }
+
+    /// Create the dialog inside `window`, returning it along with the element to focus
+    fn open(
+        self,
+        window: &Window,
+        close: Close,
+    ) -> Result<(*mut sys::UIWindow, Open<'a>, *mut sys::UIElement)> {
+        let raw_window = registry::live(window.raw, window.id)?;
+        let wrapper = unsafe { sys::LuigiRsDialogBegin(raw_window) };
+        if wrapper.is_null() {
+            return Err(Error::DialogOpen);
+        }
+
+        let mut bindings = Vec::new();
+        let mut focus = ptr::null_mut();
+        let built = unsafe { self.build(wrapper, &close, &mut bindings, &mut focus) };
+        let buttons = match built {
+            Ok(buttons) => buttons,
+            Err(error) => {
+                unsafe { sys::LuigiRsDialogEnd(raw_window) };
+                return Err(error);
+            }
+        };
+
+        unsafe {
+            keys(wrapper, raw_window, &buttons, close);
+        }
+        Ok((raw_window, Open { bindings, buttons }, focus))
+    }
+
+    /// Create the rows inside the dialog wrapper, returning its buttons
+    ///
+    /// Like `UIDialogShow`, the first button or textbox gets the focus.
+    unsafe fn build(
+        self,
+        wrapper: *mut sys::UIElement,
+        close: &Close,
+        bindings: &mut Vec<(TextBox, &'a mut String)>,
+        focus: &mut *mut sys::UIElement,
+    ) -> Result<Vec<(Button, String, Role)>> {
+        let panel = (*wrapper).children;
+        let panel = Panel::from_raw(panel, registry::track(panel));
+        let mut buttons = Vec::new();
+
+        for items in self.rows.into_iter().filter(|items| !items.is_empty()) {
+            let row = Panel::new(&panel, sys::UI_PANEL_HORIZONTAL)?;
+            (*row.raw).gap = sys::UI_SIZE_PANE_SMALL_GAP as i32;
+
+            for item in items {
+                match item {
+                    Item::Label(text) => {
+                        Label::new(&row, 0, &text)?;
+                    }
+                    Item::Textbox(text) => {
+                        let textbox = TextBox::new(&row, sys::UI_ELEMENT_H_FILL)?;
+                        sys::UITextboxReplace(
+                            textbox.raw,
+                            text.as_ptr().cast(),
+                            text.len() as isize,
+                            false,
+                        );
+                        if focus.is_null() {
+                            *focus = textbox.raw_element();
+                        }
+                        bindings.push((textbox, text));
+                    }
+                    Item::Button(label, role) => {
+                        let button = Button::new(&row, 0, &label)?;
+                        let index = buttons.len();
+                        let close = close.clone();
+                        button.invoke(Box::new(move || close(Some(index))))?;
+                        if focus.is_null() {
+                            *focus = button.raw_element();
+                        }
+                        buttons.push((button, label, role));
+                    }
+                    Item::Fill => {
+                        Spacer::new(&row, sys::UI_ELEMENT_H_FILL, 0, 0)?;
+                    }
+                    Item::Separator => {
+                        Spacer::new(&row, sys::UI_SPACER_LINE | sys::UI_ELEMENT_H_FILL, 0, 1)?;
+                    }
+                    Item::Custom(element, id) => {
+                        let element = registry::live(element, id)?;
+                        sys::UIElementChangeParent(element, row.raw_element(), ptr::null_mut());
+                    }
+                }
+            }
+        }
+        Ok(buttons)
+    }
+}
+
+/// Handle Enter and Escape, which Luigi's own dialogs leave as a TODO
+unsafe fn keys(
+    wrapper: *mut sys::UIElement,
+    window: *mut sys::UIWindow,
+    buttons: &[(Button, String, Role)],
+    close: Close,
+) {
+    let elements = buttons
+        .iter()
+        .map(|(button, _, _)| button.raw_element())
+        .collect::<Vec<_>>();
+    let find = |role| buttons.iter().position(|(_, _, r)| *r == role);
+    let default = find(Role::Default).or((!buttons.is_empty()).then_some(0));
+    let cancel = find(Role::Cancel);
+
+    let handler = move |_: &mut dyn Element, message: Message| {
+        let Message::KeyTyped(key) = message else {
+            return Response::Unhandled;
+        };
+        let (enter, escape) = unsafe { (sys::UI_KEYCODE_ENTER, sys::UI_KEYCODE_ESCAPE) };
+        let target = if key.code == enter as isize {
+            let focused = unsafe { (*window).focused };
+            elements.iter().position(|&e| e == focused).or(default)
+        } else if key.code == escape as isize {
+            cancel
+        } else {
+            return Response::Unhandled;
+        };
+        unsafe {
+            match target {
+                Some(index) => {
+                    sys::UIElementMessage(
+                        elements[index],
+                        sys::UIMessage_UI_MSG_CLICKED,
+                        0,
+                        ptr::null_mut(),
+                    );
+                }
+                // Closing without a button, like a window's close box.
+                None if key.code == escape as isize => close(None),
+                None => {}
+            }
+        }
+        Response::Handled
+    };
+    registry::set_handler(wrapper, Box::new(handler));
+}
@@ -0,0 +1,280 @@
+//! A single-threaded executor polled from inside the message loop.
+//!
+//! Wakers post to a window through [`UiSender`], so futures can be woken from any thread
+//! and are always polled on the UI thread.
+
+use crate::{registry, sys, UiSender};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+struct Task {
+    future: LocalFuture,
+    waker: Arc<TaskWaker>,
+    /// Id of the window the task is woken through
+    window: u32,
+}
+
+thread_local! {
+    static TASKS: RefCell<HashMap<u64, Task>> = RefCell::new(HashMap::new());
+    static NEXT_TASK: Cell<u64> = const { Cell::new(0) };
+    /// Windows that drop their tasks when destroyed
+    static WINDOWS: RefCell<HashSet<u32>> = RefCell::new(HashSet::new());
+}
+
+struct TaskWaker {
+    id: u64,
+    sender: UiSender,
+    scheduled: AtomicBool,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        // Only one poll is queued at a time, however often the task is woken.
+        if !self.scheduled.swap(true, Ordering::AcqRel) {
+            let id = self.id;
+            // Fails once the window is gone, and with it the loop that would poll.
+            let _ = self.sender.send(move || poll_task(id));
+        }
+    }
+}
+
+/// Run `future` on the UI thread, polling it from the message loop whenever it is woken
+///
+/// The task is dropped along with the other tasks of `window` when it is destroyed.
+///
+/// # Safety
+/// Must be called on the UI thread with a live window.
+pub(crate) unsafe fn spawn_local(
+    window: *mut sys::UIWindow,
+    future: impl Future<Output = ()> + 'static,
+) {
+    let window_id = (*window).e.id;
+    if WINDOWS.with(|windows| windows.borrow_mut().insert(window_id)) {
+        registry::listen(
+            window.cast(),
+            sys::UIMessage_UI_MSG_DESTROY,
+            Box::new(move || drop_tasks(window_id)),
+        );
+    }
+
+    let id = NEXT_TASK.with(|next| next.replace(next.get() + 1));
+    let waker = Arc::new(TaskWaker {
+        id,
+        sender: UiSender::for_window(window),
+        scheduled: AtomicBool::new(false),
+    });
+    let task = Task {
+        future: Box::pin(future),
+        waker: waker.clone(),
+        window: window_id,
+    };
+    TASKS.with(|tasks| tasks.borrow_mut().insert(id, task));
+    waker.wake();
+}
+
+/// Drop the futures of a destroyed window, which nothing can wake any more
+fn drop_tasks(window: u32) {
+    WINDOWS.with(|windows| windows.borrow_mut().remove(&window));
+    let orphaned = TASKS.with(|tasks| {
+        let mut tasks = tasks.borrow_mut();
+        let ids = tasks
+            .iter()
+            .filter(|(_, task)| task.window == window)
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+        ids.into_iter()
+            .filter_map(|id| tasks.remove(&id))
+            .collect::<Vec<_>>()
+    });
+    // Dropped after the borrow ends, as futures may own wrappers or spawn in `Drop`.
+    drop(orphaned);
+}
+
+fn poll_task(id: u64) {
+    // Taken out while polling, so the future can spawn or wake tasks itself.
+    let Some(mut task) = TASKS.with(|tasks| tasks.borrow_mut().remove(&id)) else {
+        return;
+    };
+    task.waker.scheduled.store(false, Ordering::Release);
+    let waker = Waker::from(task.waker.clone());
+    // A task whose window went away while it was polled could never be woken again.
+    if task
+        .future
+        .as_mut()
+        .poll(&mut Context::from_waker(&waker))
+        .is_pending()
+        && task.waker.sender.is_alive()
+    {
+        TASKS.with(|tasks| tasks.borrow_mut().insert(id, task));
+    }
+}
+
+struct Queue<T> {
+    items: VecDeque<T>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+/// A stream of UI events, such as the clicks of a button
+///
+/// Events that arrive while nobody is waiting are queued.
+pub struct Events<T> {
+    queue: Rc<RefCell<Queue<T>>>,
+}
+
+impl<T> Events<T> {
+    /// Wait for the next event, or `None` once the element producing them is destroyed
+    pub async fn next(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_next(cx)).await
+    }
+
+    pub(crate) fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut queue = self.queue.borrow_mut();
+        match queue.items.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if queue.closed => Poll::Ready(None),
+            None => {
+                queue.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Create a stream along with the sink that feeds it
+///
+/// The stream ends once every clone of the sink has been dropped, which happens when
+/// the element holding it is destroyed.
+pub(crate) fn events<T>() -> (EventSink<T>, Events<T>) {
+    let queue = Rc::new(RefCell::new(Queue {
+        items: VecDeque::new(),
+        closed: false,
+        waker: None,
+    }));
+    let sink = EventSink(Rc::new(SinkInner(queue.clone())));
+    (sink, Events { queue })
+}
+
+pub(crate) struct EventSink<T>(Rc<SinkInner<T>>);
+
+struct SinkInner<T>(Rc<RefCell<Queue<T>>>);
+
+impl<T> EventSink<T> {
+    pub(crate) fn push(&self, item: T) {
+        let waker = {
+            let mut queue = (self.0).0.borrow_mut();
+            queue.items.push_back(item);
+            queue.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Clone for EventSink<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Drop for SinkInner<T> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut queue = self.0.borrow_mut();
+            queue.closed = true;
+            queue.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// The item picked from a menu shown with [`crate::Menu::show_async`]
+pub struct Choice {
+    events: Events<usize>,
+}
+
+impl Choice {
+    pub(crate) fn new(events: Events<usize>) -> Self {
+        Self { events }
+    }
+}
+
+impl Future for Choice {
+    /// Index of the picked item, or `None` if the menu was dismissed
+    type Output = Option<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.events.poll_next(cx)
+    }
+}
+
+struct Slot<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// The result of a closure running on a background thread
+///
+/// Created by [`spawn_blocking`].
+pub struct Background<T> {
+    slot: Arc<Mutex<Slot<thread::Result<T>>>>,
+}
+
+/// Run `f` on a new thread and get a future for its result
+///
+/// If `f` panics, the panic is resumed when the future is polled.
+pub fn spawn_blocking<T, F>(f: F) -> Background<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let slot = Arc::new(Mutex::new(Slot {
+        value: None,
+        waker: None,
+    }));
+    let result = slot.clone();
+    thread::spawn(move || {
+        let value = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+        let waker = {
+            let mut slot = result.lock().unwrap_or_else(PoisonError::into_inner);
+            slot.value = Some(value);
+            slot.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    });
+    Background { slot }
+}
+
+impl<T> Future for Background<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut slot = self.slot.lock().unwrap_or_else(PoisonError::into_inner);
+        match slot.value.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => std::panic::resume_unwind(payload),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
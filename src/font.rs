@@ -0,0 +1,120 @@
+//! Fonts created with `UIFontCreate`.
+
+use crate::{sys, Error, Result};
+use std::cell::OnceCell;
+use std::ffi::CString;
+use std::path::Path;
+
+thread_local! {
+    static BITMAP: OnceCell<Font> = const { OnceCell::new() };
+}
+
+/// A font used to measure and draw text
+///
+/// Luigi never frees fonts, so a `Font` is a cheap handle that stays valid for the
+/// rest of the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Font {
+    raw: *mut sys::UIFont,
+}
+
+impl Font {
+    /// Load a font file at the given point size
+    ///
+    /// Requires the `freetype` feature and [`crate::init`] to have been called. Without
+    /// the feature this always returns the built-in 9x16 bitmap font.
+    ///
+    /// With FreeType, every successful call allocates a new font that is kept for the
+    /// rest of the process, so load each font once and keep the handle.
+    ///
+    /// # Errors
+    /// [`Error::InvalidFont`] if FreeType cannot open the file.
+    pub fn load(path: impl AsRef<Path>, size: u32) -> Result<Self> {
+        let path = path_to_c(path.as_ref())?;
+        if !cfg!(feature = "freetype") {
+            // Luigi ignores the path without FreeType, so share the one bitmap font.
+            return Ok(Self::bitmap());
+        }
+        let raw = unsafe { sys::UIFontCreate(path.as_ptr(), size) };
+        if raw.is_null() {
+            return Err(Error::CreateFailed);
+        }
+        // Luigi silently falls back to the bitmap font when FreeType fails.
+        #[cfg(feature = "freetype")]
+        if unsafe { !(*raw).isFreeType } {
+            unsafe { sys::LuigiRsFontFree(raw) };
+            return Err(Error::InvalidFont);
+        }
+        Ok(Self { raw })
+    }
+
+    /// Load the font chosen at build time, falling back to the bitmap font
+    ///
+    /// That is `LUIGI_FONT_PATH`, or a DejaVu Sans Mono found by the build script.
+    pub(crate) fn load_default(size: u32) -> Self {
+        #[cfg(feature = "freetype")]
+        if let Ok(font) = Self::load(env!("LUIGI_RS_FONT_PATH"), size) {
+            return font;
+        }
+        let _ = size;
+        Self::bitmap()
+    }
+
+    /// Get the built-in 9x16 bitmap font
+    ///
+    /// The font is created on first use and shared by later calls.
+    pub fn bitmap() -> Self {
+        BITMAP.with(|bitmap| {
+            *bitmap.get_or_init(|| Self {
+                raw: unsafe { sys::UIFontCreate(std::ptr::null(), 0) },
+            })
+        })
+    }
+
+    /// Get the font used for text that does not have its own font
+    ///
+    /// Returns `None` before [`crate::init`] is called.
+    pub fn active() -> Option<Self> {
+        let previous = unsafe { sys::UIFontActivate(std::ptr::null_mut()) };
+        unsafe { sys::UIFontActivate(previous) };
+        Self::from_raw(previous)
+    }
+
+    /// Make this the font used for text that does not have its own font
+    ///
+    /// Returns the previously active font so it can be restored. Elements measure
+    /// text when laid out, so refresh windows after switching.
+    pub fn activate(&self) -> Option<Font> {
+        Self::from_raw(unsafe { sys::UIFontActivate(self.raw) })
+    }
+
+    /// Width of a character in pixels; the font is treated as monospaced
+    pub fn glyph_width(&self) -> i32 {
+        unsafe { (*self.raw).glyphWidth }
+    }
+
+    /// Height of a line in pixels
+    pub fn glyph_height(&self) -> i32 {
+        unsafe { (*self.raw).glyphHeight }
+    }
+
+    pub(crate) fn from_raw(raw: *mut sys::UIFont) -> Option<Self> {
+        (!raw.is_null()).then_some(Self { raw })
+    }
+
+    pub(crate) fn as_raw(&self) -> *mut sys::UIFont {
+        self.raw
+    }
+}
+
+#[cfg(unix)]
+fn path_to_c(path: &Path) -> Result<CString> {
+    use std::os::unix::ffi::OsStrExt;
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| Error::InvalidString)
+}
+
+#[cfg(not(unix))]
+fn path_to_c(path: &Path) -> Result<CString> {
+    let path = path.to_str().ok_or(Error::InvalidString)?;
+    CString::new(path).map_err(|_| Error::InvalidString)
+}
@@ -0,0 +1,313 @@
+// Headless backend, included by src/wrapper.c after the Luigi implementation.
+//
+// Windows paint into their `bits` and never reach a display. Input is synthesized
+// through the LuigiRsHeadless* functions, and posted messages wait in a queue.
+
+#ifdef _WIN32
+#include <windows.h>
+#else
+#include <pthread.h>
+#endif
+
+const int UI_KEYCODE_A = 'a';
+const int UI_KEYCODE_BACKSPACE = 0x08;
+const int UI_KEYCODE_DELETE = 0x7F;
+const int UI_KEYCODE_DOWN = 0x100;
+const int UI_KEYCODE_END = 0x101;
+const int UI_KEYCODE_ENTER = 0x0D;
+const int UI_KEYCODE_ESCAPE = 0x1B;
+const int UI_KEYCODE_F1 = 0x110;
+const int UI_KEYCODE_HOME = 0x102;
+const int UI_KEYCODE_LEFT = 0x103;
+const int UI_KEYCODE_RIGHT = 0x104;
+const int UI_KEYCODE_SPACE = ' ';
+const int UI_KEYCODE_TAB = 0x09;
+const int UI_KEYCODE_UP = 0x105;
+const int UI_KEYCODE_INSERT = 0x106;
+const int UI_KEYCODE_0 = '0';
+
+typedef struct LuigiRsPosted {
+    UIWindow *window;
+    UIMessage message;
+    void *dp;
+} LuigiRsPosted;
+
+static struct {
+#ifdef _WIN32
+    SRWLOCK lock;
+    CONDITION_VARIABLE posted;
+#else
+    pthread_mutex_t lock;
+    pthread_cond_t posted;
+#endif
+    LuigiRsPosted *items;
+    size_t count, allocated;
+} luigiRsQueue = {
+#ifdef _WIN32
+    SRWLOCK_INIT, CONDITION_VARIABLE_INIT, NULL, 0, 0
+#else
+    PTHREAD_MUTEX_INITIALIZER, PTHREAD_COND_INITIALIZER, NULL, 0, 0
+#endif
+};
+
+static char *luigiRsPasteText;
+
+static void LuigiRsQueueLock() {
+#ifdef _WIN32
+    AcquireSRWLockExclusive(&luigiRsQueue.lock);
+#else
+    pthread_mutex_lock(&luigiRsQueue.lock);
+#endif
+}
+
+static void LuigiRsQueueUnlock() {
+#ifdef _WIN32
+    ReleaseSRWLockExclusive(&luigiRsQueue.lock);
+#else
+    pthread_mutex_unlock(&luigiRsQueue.lock);
+#endif
+}
+
+static bool LuigiRsWindowExists(UIWindow *window) {
+    for (UIWindow *w = ui.windows; w; w = w->next) {
+        if (w == window) return true;
+    }
+
+    return false;
+}
+
+// Lay out windows whose size changed, as a ConfigureNotify would on X11.
+static void LuigiRsHeadlessConfigure() {
+    bool changed = false;
+
+    for (UIWindow *window = ui.windows; window; window = window->next) {
+        UIRectangle bounds = UI_RECT_2S(window->width, window->height);
+        if (UIRectangleEquals(window->e.bounds, bounds)) continue;
+        window->e.bounds = bounds;
+        window->e.clip = bounds;
+        UIElementMessage(&window->e, UI_MSG_LAYOUT, 0, 0);
+        UIElementRepaint(&window->e, NULL);
+        changed = true;
+    }
+
+    if (changed) _UIUpdate();
+}
+
+void LuigiRsHeadlessResize(UIWindow *window, int width, int height) {
+    window->width = width > 0 ? width : 1;
+    window->height = height > 0 ? height : 1;
+    window->bits = (uint32_t *) UI_REALLOC(window->bits, window->width * window->height * 4);
+    LuigiRsHeadlessConfigure();
+}
+
+int _UIWindowMessage(UIElement *element, UIMessage message, int di, void *dp) {
+    if (message == UI_MSG_DESTROY) {
+        _UIWindowDestroyCommon((UIWindow *) element);
+    }
+
+    return _UIWindowMessageCommon(element, message, di, dp);
+}
+
+UIWindow *UIWindowCreate(UIWindow *owner, uint32_t flags, const char *cTitle, int width, int height) {
+    (void) cTitle;
+    _UIMenusClose();
+
+    UIWindow *window = (UIWindow *) UIElementCreate(sizeof(UIWindow), NULL, flags | UI_ELEMENT_WINDOW, _UIWindowMessage, "Window");
+    _UIWindowAdd(window);
+    if (owner) window->scale = owner->scale;
+
+    // Laid out on the next loop step or synthesized input, once the contents exist.
+    window->width = (flags & UI_WINDOW_MENU) ? 1 : width ? width : 800;
+    window->height = (flags & UI_WINDOW_MENU) ? 1 : height ? height : 600;
+    window->bits = (uint32_t *) UI_CALLOC(window->width * window->height * 4);
+    return window;
+}
+
+void _UIClipboardWriteText(UIWindow *window, char *text) {
+    (void) window;
+    UI_FREE(luigiRsPasteText);
+    luigiRsPasteText = text;
+}
+
+char *_UIClipboardReadTextStart(UIWindow *window, size_t *bytes) {
+    (void) window;
+    if (!luigiRsPasteText) return NULL;
+    *bytes = strlen(luigiRsPasteText);
+    char *copy = (char *) UI_MALLOC(*bytes + 1);
+    memcpy(copy, luigiRsPasteText, *bytes + 1);
+    return copy;
+}
+
+void _UIClipboardReadTextEnd(UIWindow *window, char *text) {
+    (void) window;
+    UI_FREE(text);
+}
+
+void UIInitialise() {
+    _UIInitialiseCommon();
+}
+
+void _UIWindowSetCursor(UIWindow *window, int cursor) {
+    (void) window;
+    (void) cursor;
+}
+
+void _UIWindowEndPaint(UIWindow *window, UIPainter *painter) {
+    // The painter drew straight into window->bits, which is the framebuffer.
+    (void) window;
+    (void) painter;
+}
+
+void _UIWindowGetScreenPosition(UIWindow *window, int *x, int *y) {
+    // Every window sits at the origin of an unbounded screen.
+    (void) window;
+    *x = *y = 0;
+}
+
+void UIMenuShow(UIMenu *menu) {
+    int width, height;
+    _UIMenuPrepare(menu, &width, &height);
+    LuigiRsHeadlessResize(menu->e.window, width, height);
+}
+
+void UIWindowPack(UIWindow *window, int width) {
+    if (!width) width = UIElementMessage(window->e.children, UI_MSG_GET_WIDTH, 0, 0);
+    int height = UIElementMessage(window->e.children, UI_MSG_GET_HEIGHT, width, 0);
+    LuigiRsHeadlessResize(window, width, height);
+}
+
+bool _UIMessageLoopSingle(int *result) {
+    (void) result;
+    LuigiRsHeadlessConfigure();
+
+    LuigiRsQueueLock();
+
+    if (ui.animating && !luigiRsQueue.count) {
+        LuigiRsQueueUnlock();
+        _UIProcessAnimations();
+        return true;
+    }
+
+    while (!luigiRsQueue.count) {
+#ifdef _WIN32
+        SleepConditionVariableSRW(&luigiRsQueue.posted, &luigiRsQueue.lock, INFINITE, 0);
+#else
+        pthread_cond_wait(&luigiRsQueue.posted, &luigiRsQueue.lock);
+#endif
+    }
+
+    LuigiRsPosted posted = luigiRsQueue.items[0];
+    luigiRsQueue.count--;
+    memmove(luigiRsQueue.items, luigiRsQueue.items + 1, luigiRsQueue.count * sizeof(LuigiRsPosted));
+    LuigiRsQueueUnlock();
+
+    // Dropped if the window went away in the meantime, like events for a destroyed X11 window.
+    if (LuigiRsWindowExists(posted.window)) {
+        UIElementMessage(&posted.window->e, posted.message, 0, posted.dp);
+        _UIUpdate();
+    }
+
+    return true;
+}
+
+void UIWindowPostMessage(UIWindow *window, UIMessage message, void *dp) {
+    LuigiRsQueueLock();
+
+    if (luigiRsQueue.count == luigiRsQueue.allocated) {
+        luigiRsQueue.allocated = luigiRsQueue.allocated ? luigiRsQueue.allocated * 2 : 16;
+        luigiRsQueue.items = (LuigiRsPosted *) UI_REALLOC(luigiRsQueue.items, luigiRsQueue.allocated * sizeof(LuigiRsPosted));
+    }
+
+    LuigiRsPosted posted = { window, message, dp };
+    luigiRsQueue.items[luigiRsQueue.count++] = posted;
+#ifdef _WIN32
+    WakeConditionVariable(&luigiRsQueue.posted);
+#else
+    pthread_cond_signal(&luigiRsQueue.posted);
+#endif
+    LuigiRsQueueUnlock();
+}
+
+bool LuigiRsHeadlessPending() {
+    LuigiRsQueueLock();
+    bool pending = luigiRsQueue.count != 0;
+    LuigiRsQueueUnlock();
+    return pending;
+}
+
+bool LuigiRsHeadlessWait(int timeout) {
+    LuigiRsQueueLock();
+
+#ifdef _WIN32
+    if (!luigiRsQueue.count) {
+        SleepConditionVariableSRW(&luigiRsQueue.posted, &luigiRsQueue.lock, timeout, 0);
+    }
+#else
+    struct timespec deadline;
+    clock_gettime(CLOCK_REALTIME, &deadline);
+    deadline.tv_sec += timeout / 1000;
+    deadline.tv_nsec += (long) (timeout % 1000) * 1000000;
+
+    if (deadline.tv_nsec >= 1000000000) {
+        deadline.tv_sec++;
+        deadline.tv_nsec -= 1000000000;
+    }
+
+    while (!luigiRsQueue.count) {
+        if (pthread_cond_timedwait(&luigiRsQueue.posted, &luigiRsQueue.lock, &deadline)) break;
+    }
+#endif
+
+    bool pending = luigiRsQueue.count != 0;
+    LuigiRsQueueUnlock();
+    return pending;
+}
+
+// Synthesized input, mirroring how the X11 backend turns events into messages
+
+void LuigiRsHeadlessMouse(UIWindow *window, int x, int y, UIMessage message, int di) {
+    LuigiRsHeadlessConfigure();
+
+    // A real pointer moves before its buttons change somewhere else, and Luigi
+    // only updates the hovered element on movement.
+    if (message != UI_MSG_MOUSE_MOVE && (window->cursorX != x || window->cursorY != y)) {
+        window->cursorX = x;
+        window->cursorY = y;
+        _UIWindowInputEvent(window, UI_MSG_MOUSE_MOVE, 0, 0);
+    }
+
+    window->cursorX = x;
+    window->cursorY = y;
+    _UIWindowInputEvent(window, message, di, 0);
+}
+
+void LuigiRsHeadlessKey(UIWindow *window, int code, const char *text, int textBytes) {
+    LuigiRsHeadlessConfigure();
+    UIKeyTyped m = { 0 };
+    m.code = code;
+    m.text = (char *) text;
+    m.textBytes = textBytes;
+    _UIWindowInputEvent(window, UI_MSG_KEY_TYPED, 0, &m);
+}
+
+void LuigiRsHeadlessModifiers(UIWindow *window, bool ctrl, bool shift, bool alt) {
+    LuigiRsHeadlessConfigure();
+    window->ctrl = ctrl;
+    window->shift = shift;
+    window->alt = alt;
+    _UIWindowInputEvent(window, UI_MSG_MOUSE_MOVE, 0, 0);
+}
+
+void LuigiRsHeadlessClose(UIWindow *window) {
+    if (UIElementMessage(&window->e, UI_MSG_WINDOW_CLOSE, 0, 0)) {
+        _UIUpdate();
+    } else {
+        ui.quit = true;
+    }
+}
+
+void LuigiRsHeadlessDropFiles(UIWindow *window, int count, char **files) {
+    LuigiRsHeadlessConfigure();
+    UIElementMessage(&window->e, UI_MSG_WINDOW_DROP_FILES, count, files);
+    _UIUpdate();
+}
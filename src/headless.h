@@ -0,0 +1,17 @@
+// Platform definitions for the headless backend, which luigi.h does not know about.
+// Included by src/wrapper.c in place of UI_LINUX or UI_WINDOWS.
+
+#include <stdlib.h>
+#include <string.h>
+#include <assert.h>
+#include <time.h>
+#include <math.h>
+
+#define UI_ASSERT assert
+#define UI_CALLOC(x) calloc(1, (x))
+#define UI_FREE free
+#define UI_MALLOC malloc
+#define UI_REALLOC realloc
+#define UI_CLOCK clock
+#define UI_CLOCKS_PER_SECOND CLOCKS_PER_SEC
+#define UI_CLOCK_T clock_t
@@ -0,0 +1,145 @@
+//! Synthesized input for the headless backend.
+//!
+//! With the `headless` feature, windows paint into an in-memory framebuffer instead of
+//! an X11 or Win32 window, and nothing arrives from the user. These methods feed input
+//! through the same path the platform backends use, so elements see ordinary messages.
+
+use crate::{registry, sys, unwind, Error, Key, Result, Window};
+use std::ffi::{c_char, CString};
+use std::path::PathBuf;
+
+/// A mouse button for synthesized input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+impl MouseButton {
+    fn messages(self) -> (sys::UIMessage, sys::UIMessage) {
+        match self {
+            MouseButton::Left => (
+                sys::UIMessage_UI_MSG_LEFT_DOWN,
+                sys::UIMessage_UI_MSG_LEFT_UP,
+            ),
+            MouseButton::Middle => (
+                sys::UIMessage_UI_MSG_MIDDLE_DOWN,
+                sys::UIMessage_UI_MSG_MIDDLE_UP,
+            ),
+            MouseButton::Right => (
+                sys::UIMessage_UI_MSG_RIGHT_DOWN,
+                sys::UIMessage_UI_MSG_RIGHT_UP,
+            ),
+        }
+    }
+}
+
+impl Window {
+    /// Change the size of the framebuffer, laying out and repainting the window
+    pub fn resize(&self, width: i32, height: i32) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe { sys::LuigiRsHeadlessResize(window, width, height) };
+        unwind::resume();
+        Ok(())
+    }
+
+    /// Move the mouse cursor to `x`, `y` in window coordinates
+    pub fn mouse_move(&self, x: i32, y: i32) -> Result<()> {
+        self.mouse(x, y, sys::UIMessage_UI_MSG_MOUSE_MOVE, 0)
+    }
+
+    /// Press `button` at `x`, `y`, moving the cursor there first
+    pub fn mouse_down(&self, x: i32, y: i32, button: MouseButton) -> Result<()> {
+        self.mouse(x, y, button.messages().0, 0)
+    }
+
+    /// Release `button` at `x`, `y`, moving the cursor there first
+    pub fn mouse_up(&self, x: i32, y: i32, button: MouseButton) -> Result<()> {
+        self.mouse(x, y, button.messages().1, 0)
+    }
+
+    /// Press and release `button` at `x`, `y`
+    pub fn click(&self, x: i32, y: i32, button: MouseButton) -> Result<()> {
+        self.mouse_down(x, y, button)?;
+        self.mouse_up(x, y, button)
+    }
+
+    /// Turn the mouse wheel at `x`, `y`, positive values scroll down
+    ///
+    /// One notch of the wheel is 72 on X11.
+    pub fn mouse_wheel(&self, x: i32, y: i32, delta: i32) -> Result<()> {
+        self.mouse(x, y, sys::UIMessage_UI_MSG_MOUSE_WHEEL, delta)
+    }
+
+    fn mouse(&self, x: i32, y: i32, message: sys::UIMessage, di: i32) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe { sys::LuigiRsHeadlessMouse(window, x, y, message, di) };
+        unwind::resume();
+        Ok(())
+    }
+
+    /// Press `key` with the current modifiers, see [`Window::set_modifiers`]
+    pub fn press_key(&self, key: Key) -> Result<()> {
+        self.key(key.code(), key.text())
+    }
+
+    /// Type `text` one character at a time into the focused element
+    ///
+    /// Characters without a key of their own are sent as text with a key code of 0,
+    /// like an input method would.
+    pub fn type_text(&self, text: &str) -> Result<()> {
+        for c in text.chars() {
+            match Key::for_char(c) {
+                Some(key) => self.press_key(key)?,
+                None => self.key(0, Some(c))?,
+            }
+        }
+        Ok(())
+    }
+
+    fn key(&self, code: i32, text: Option<char>) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        let mut buffer = [0; 4];
+        let text = text.map_or("", |c| c.encode_utf8(&mut buffer));
+        let bytes = text.len() as i32;
+        unsafe { sys::LuigiRsHeadlessKey(window, code, text.as_ptr() as *const c_char, bytes) };
+        unwind::resume();
+        Ok(())
+    }
+
+    /// Hold down or release the modifier keys for the following input
+    pub fn set_modifiers(&self, ctrl: bool, shift: bool, alt: bool) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe { sys::LuigiRsHeadlessModifiers(window, ctrl, shift, alt) };
+        unwind::resume();
+        Ok(())
+    }
+
+    /// Ask to close the window, as the close button of a window manager would
+    ///
+    /// This goes through [`Window::on_close`]. Closing the last window ends the
+    /// message loop.
+    pub fn request_close(&self) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe { sys::LuigiRsHeadlessClose(window) };
+        unwind::resume();
+        Ok(())
+    }
+
+    /// Drop `paths` onto the window, see [`Window::on_drop_files`]
+    pub fn drop_files(&self, paths: &[PathBuf]) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        let paths = paths
+            .iter()
+            .map(|path| CString::new(path.to_string_lossy().into_owned()))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::InvalidString)?;
+        let mut pointers: Vec<*mut c_char> = paths.iter().map(|p| p.as_ptr().cast_mut()).collect();
+        unsafe {
+            sys::LuigiRsHeadlessDropFiles(window, pointers.len() as i32, pointers.as_mut_ptr())
+        };
+        unwind::resume();
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+//! Pixel buffers captured from windows.
+
+/// An image of 32-bit `0xAARRGGBB` pixels, stored row by row
+///
+/// Luigi paints colours without alpha, so only the low 24 bits of captured pixels
+/// are meaningful.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u32>,
+}
+
+impl Image {
+    /// Wrap `pixels`, which must hold `width * height` values
+    ///
+    /// # Panics
+    /// If `pixels` has the wrong length.
+    pub fn new(width: usize, height: usize, pixels: Vec<u32>) -> Self {
+        assert_eq!(pixels.len(), width * height, "wrong number of pixels");
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Get the width in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the height in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get all pixels, row by row
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    /// Get the pixel at `x`, `y`, or `None` if it is outside the image
+    pub fn pixel(&self, x: usize, y: usize) -> Option<u32> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Compare the colour channels of two images, ignoring alpha
+    ///
+    /// Pixels match when no channel differs by more than `tolerance`. Returns `None`
+    /// if every pixel matches, otherwise the number of differing pixels and an image
+    /// showing them in red over a faded copy of `self`. Pixels outside one of the
+    /// images count as differing.
+    pub fn diff(&self, other: &Image, tolerance: u8) -> Option<(usize, Image)> {
+        let width = self.width.max(other.width);
+        let height = self.height.max(other.height);
+        let mut pixels = Vec::with_capacity(width * height);
+        let mut differing = 0;
+
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = match (self.pixel(x, y), other.pixel(x, y)) {
+                    (Some(a), Some(b)) if channels_match(a, b, tolerance) => fade(a),
+                    _ => {
+                        differing += 1;
+                        0xFFFF0000
+                    }
+                };
+                pixels.push(pixel);
+            }
+        }
+
+        (differing > 0).then(|| (differing, Image::new(width, height, pixels)))
+    }
+}
+
+fn channels_match(a: u32, b: u32, tolerance: u8) -> bool {
+    [0, 8, 16].iter().all(|shift| {
+        let a = (a >> shift) as u8;
+        let b = (b >> shift) as u8;
+        a.abs_diff(b) <= tolerance
+    })
+}
+
+/// Blend a pixel two thirds of the way to white
+fn fade(pixel: u32) -> u32 {
+    let channel = |shift: u32| {
+        let value = (pixel >> shift) & 0xFF;
+        (0xFF - (0xFF - value) / 3) << shift
+    };
+    0xFF000000 | channel(16) | channel(8) | channel(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: usize, height: usize, pixel: u32) -> Image {
+        Image::new(width, height, vec![pixel; width * height])
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let image = solid(3, 2, 0xFF336699);
+        assert_eq!(image.diff(&image.clone(), 0), None);
+    }
+
+    #[test]
+    fn tolerance_boundary() {
+        let a = solid(2, 2, 0xFF808080);
+        // Each channel in turn, differing by exactly the tolerance and then one more.
+        for shift in [0, 8, 16] {
+            let b = solid(2, 2, 0xFF808080 + (4 << shift));
+            assert_eq!(a.diff(&b, 4), None);
+            let c = solid(2, 2, 0xFF808080 + (5 << shift));
+            assert_eq!(a.diff(&c, 4).map(|(differing, _)| differing), Some(4));
+        }
+    }
+
+    #[test]
+    fn alpha_is_ignored() {
+        assert_eq!(
+            solid(1, 1, 0x00123456).diff(&solid(1, 1, 0xFF123456), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn differing_pixels_are_marked() {
+        let a = solid(2, 1, 0xFF000000);
+        let mut pixels = a.pixels().to_vec();
+        pixels[1] = 0xFFFFFFFF;
+        let b = Image::new(2, 1, pixels);
+
+        let (differing, diff) = a.diff(&b, 0).unwrap();
+        assert_eq!(differing, 1);
+        assert_eq!(diff.pixel(0, 0), Some(fade(0xFF000000)));
+        assert_eq!(diff.pixel(1, 0), Some(0xFFFF0000));
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let a = solid(2, 2, 0xFF000000);
+        let b = solid(3, 1, 0xFF000000);
+
+        let (differing, diff) = a.diff(&b, 255).unwrap();
+        // Only the two pixels present in both images match.
+        assert_eq!(differing, 3 * 2 - 2);
+        assert_eq!((diff.width(), diff.height()), (3, 2));
+        assert_eq!(diff.pixel(2, 0), Some(0xFFFF0000));
+        assert_eq!(diff.pixel(0, 1), Some(0xFFFF0000));
+    }
+}
@@ -3,10 +3,64 @@
 //! This library provides a safe wrapper around the native C Luigi UI library,
 //! offering an idiomatic Rust interface while maintaining all the original functionality.
 
+mod animation;
+mod app;
+#[cfg(feature = "automation")]
+mod automation;
+mod custom;
+#[cfg(feature = "debug")]
+mod debug;
+mod dialog;
+mod executor;
+mod font;
+#[cfg(feature = "headless")]
+mod headless;
+mod image;
+mod message;
+mod painter;
+mod pump;
+mod registry;
+mod sender;
+#[cfg(feature = "snapshot")]
+mod snapshot;
 mod sys;
+mod theme;
+mod timer;
+mod tree;
+mod unwind;
 
 use std::ffi::{c_void, CString};
+use std::future::Future;
+use std::path::PathBuf;
 use std::ptr;
+use std::rc::Rc;
+use std::time::Duration;
+
+pub use animation::{lerp, tween, Animation, Easing};
+pub use app::App;
+#[cfg(feature = "automation")]
+pub use automation::Automation;
+pub use custom::{Custom, CustomElement, ElementContext, Mouse};
+#[cfg(feature = "debug")]
+pub use debug::{InspectorLogger, Overdraw};
+pub use dialog::{Dialog, DialogResult, PendingDialog};
+pub use executor::{spawn_blocking, Background, Choice, Events};
+pub use font::Font;
+#[cfg(feature = "headless")]
+pub use headless::MouseButton;
+pub use image::Image;
+pub use message::{ClientParent, FindByPoint, Key, KeyTyped, Message, TableGetItem};
+pub use painter::{Color, Painter, Rect, StringSelection};
+#[cfg(unix)]
+pub use pump::connection_fd;
+pub use pump::{is_animating, next_timer, pump, pump_pending, Pump};
+pub use registry::{Handle, Response};
+pub use sender::UiSender;
+#[cfg(feature = "snapshot")]
+pub use snapshot::{assert_snapshot, compare_snapshot, UPDATE_SNAPSHOTS_VAR};
+pub use theme::{set_theme, theme, Theme};
+pub use timer::Timer;
+pub use tree::{AnyElement, Children};
 
 // Re-export common constants
 pub use sys::{
@@ -14,14 +68,10 @@ pub use sys::{
     UI_BUTTON_DROP_DOWN, UI_BUTTON_MENU_ITEM, UI_BUTTON_SMALL, UI_ELEMENT_H_FILL,
     UI_ELEMENT_PARENT_PUSH, UI_ELEMENT_V_FILL, UI_PANEL_BORDER, UI_PANEL_EXPAND, UI_PANEL_GRAY,
     UI_PANEL_HORIZONTAL, UI_PANEL_MEDIUM_SPACING, UI_PANEL_SCROLL, UI_PANEL_SMALL_SPACING,
-    UI_PANEL_WHITE, UI_WINDOW_CENTER_IN_OWNER, UI_WINDOW_INSPECTOR, UI_WINDOW_MAXIMIZE,
-    UI_WINDOW_MENU,
+    UI_PANEL_WHITE, UI_SCROLL_BAR_HORIZONTAL, UI_SPACER_LINE, UI_SPLIT_PANE_VERTICAL,
+    UI_WINDOW_CENTER_IN_OWNER, UI_WINDOW_INSPECTOR, UI_WINDOW_MAXIMIZE, UI_WINDOW_MENU,
 };
 
-// Define message constants directly since they don't exist in sys
-pub const UI_MSG_TABLE_GET_ITEM: i32 = 51; // These values should match the C enum
-pub const UI_MSG_LEFT_DOWN: i32 = 11; // These values should match the C enum
-
 /// Error types that can occur in Luigi operations
 #[derive(Debug)]
 pub enum Error {
@@ -31,6 +81,26 @@ pub enum Error {
     InvalidString,
     /// Failed to create a UI element
     CreateFailed,
+    /// Luigi is already animating an element of its own, such as a held scroll button
+    AnimationBusy,
+    /// The element has already been destroyed
+    Destroyed,
+    /// A line of a theme file could not be parsed (counting from 1)
+    InvalidTheme { line: usize },
+    /// A font file could not be loaded
+    InvalidFont,
+    /// The window is already showing a dialog
+    DialogOpen,
+    /// Reading or writing a file failed
+    Io(std::io::Error),
+    /// An image file could not be decoded or encoded
+    InvalidImage,
+    /// A capture differs from its golden image in `differing` pixels, marked in the image at `diff`
+    SnapshotMismatch { differing: usize, diff: PathBuf },
+    /// A global logger has already been installed
+    LoggerInstalled,
+    /// The element is hidden or clipped away entirely, so it cannot be clicked
+    NotVisible,
 }
 
 /// Result type for Luigi operations
@@ -39,27 +109,142 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// Common trait implemented by all UI elements
 pub trait Element {
     /// Get the raw pointer to the underlying UIElement
+    ///
+    /// The pointer dangles once the element is destroyed; see [`Element::live_element`].
     fn raw_element(&self) -> *mut sys::UIElement;
 
+    /// Get the `UIElement.id` the element had when it was wrapped
+    fn id(&self) -> u32;
+
+    /// Check whether the element still exists
+    fn is_alive(&self) -> bool {
+        registry::is_alive(self.raw_element(), self.id())
+    }
+
+    /// Get the raw pointer to the underlying UIElement, if it still exists
+    fn live_element(&self) -> Result<*mut sys::UIElement> {
+        registry::live(self.raw_element(), self.id())
+    }
+
     /// Destroy this element and remove it from the UI hierarchy
-    fn destroy(&mut self) {
-        unsafe { sys::UIElementDestroy(self.raw_element()) }
+    fn destroy(&mut self) -> Result<()> {
+        unsafe { sys::UIElementDestroy(self.live_element()?) };
+        Ok(())
     }
 
     /// Refresh this element's layout and appearance
-    fn refresh(&mut self) {
-        unsafe { sys::UIElementRefresh(self.raw_element()) }
+    fn refresh(&mut self) -> Result<()> {
+        unsafe { sys::UIElementRefresh(self.live_element()?) };
+        Ok(())
+    }
+
+    /// Get the parent element, or `None` for a window
+    ///
+    /// Elements that Luigi creates itself are skipped, see [`Children`].
+    fn parent(&self) -> Result<Option<AnyElement>> {
+        Ok(unsafe { tree::parent(self.live_element()?) })
+    }
+
+    /// Iterate over the direct children, in layout order
+    ///
+    /// Elements that Luigi creates itself are skipped, see [`Children`].
+    fn children(&self) -> Result<Children> {
+        Ok(unsafe { Children::new(self.live_element()?) })
+    }
+
+    /// Get the window containing this element
+    ///
+    /// Returns `None` for elements of windows that Luigi creates itself, such as the
+    /// inspector, since wrapping those would hook them.
+    fn window(&self) -> Result<Option<Window>> {
+        unsafe {
+            let window = (*self.live_element()?).window;
+            let id = (*window).e.id;
+            Ok(registry::is_alive(window.cast(), id).then(|| Window::from_raw(window.cast(), id)))
+        }
+    }
+
+    /// Get the bounds in window coordinates, as of the last layout
+    fn bounds(&self) -> Result<Rect> {
+        Ok(unsafe { (*self.live_element()?).bounds.into() })
+    }
+
+    /// Get the bounds in screen coordinates, as used for positioning windows
+    fn screen_bounds(&self) -> Result<Rect> {
+        Ok(unsafe { sys::UIElementScreenBounds(self.live_element()?).into() })
+    }
+
+    /// Get the name of the element's class, such as `"Button"`
+    fn class_name(&self) -> Result<String> {
+        Ok(unsafe { tree::class_name(self.live_element()?) })
+    }
+
+    /// Describe this element and its descendants, one per line with their bounds
+    ///
+    /// Meant for debugging layouts without the inspector, e.g. with `print!`. Unlike
+    /// [`Element::children`], this includes the elements Luigi creates itself.
+    fn dump_tree(&self) -> Result<String> {
+        Ok(unsafe { tree::dump(self.live_element()?) })
+    }
+
+    /// Call `callback` every frame until it returns `false` or the animation is stopped
+    ///
+    /// The message loop keeps spinning while anything animates, so callbacks should
+    /// repaint what they change. Animations end when the element is destroyed.
+    fn animate<F>(&self, callback: F) -> Result<Animation>
+    where
+        F: FnMut() -> bool + 'static,
+        Self: Sized,
+    {
+        let element = self.live_element()?;
+        unsafe { animation::start(element, self.id(), Box::new(callback)) }
+    }
+
+    /// Attach a handler that sees every message sent to this element
+    ///
+    /// The handler runs before the element's own message class. Returning
+    /// [`Response::Unhandled`] keeps the default behaviour, anything else overrides it.
+    /// Attaching a new handler replaces the previous one.
+    fn on_message<F>(&self, handler: F) -> Result<()>
+    where
+        F: FnMut(&mut dyn Element, Message) -> Response + 'static,
+        Self: Sized,
+    {
+        unsafe { registry::set_handler(self.live_element()?, Box::new(handler)) };
+        Ok(())
+    }
+}
+
+/// Typed element wrappers that can be rebuilt from a weak [`Handle`]
+pub trait Widget: Element + Sized {
+    /// Wrap a raw element without checking its type
+    ///
+    /// # Safety
+    /// `raw` must point to an element of the type this wrapper expects, with the given id.
+    #[doc(hidden)]
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self;
+
+    /// Get a weak handle to this element that can be stored and upgraded later
+    fn handle(&self) -> Handle<Self> {
+        unsafe { Handle::new(self.raw_element(), self.id()) }
     }
 }
 
 /// Handler for UI element events
 pub trait EventHandler {
-    fn handle(&self, element: &mut dyn Element, message: i32, data: &str) -> String;
+    /// Handle a message sent to the element
+    ///
+    /// The return value is passed back to Luigi; return 0 to get the default behaviour.
+    fn handle(&self, element: &mut dyn Element, message: Message) -> i32;
 }
 
 /// A top-level window containing UI elements
+///
+/// Elements may only be touched from the thread that called [`init`], so wrappers are
+/// neither `Send` nor `Sync`. Use [`Window::sender`] to reach the UI from other threads.
 pub struct Window {
     raw: *mut sys::UIWindow,
+    id: u32,
 }
 
 impl Window {
@@ -71,36 +256,276 @@ impl Window {
     /// * `height` - Window height in pixels (0 for default)
     /// * `flags` - Window creation flags
     pub fn new(title: &str, width: i32, height: i32, flags: u32) -> Result<Self> {
-        let title = CString::new(title).map_err(|_| Error::InvalidString)?;
-        let raw =
-            unsafe { sys::UIWindowCreate(ptr::null_mut(), flags, title.as_ptr(), width, height) };
-        if raw.is_null() {
-            return Err(Error::CreateFailed);
-        }
-        Ok(Self { raw })
+        Self::builder()
+            .title(title)
+            .size(width, height)
+            .flags(flags)
+            .build()
+    }
+
+    /// Start building a window, for options such as an owner window
+    pub fn builder<'a>() -> WindowBuilder<'a> {
+        WindowBuilder::default()
     }
 
     /// Register a keyboard shortcut for this window
     ///
     /// # Arguments
     /// * `shortcut` - The shortcut to register
-    pub fn register_shortcut(&mut self, shortcut: Shortcut) {
+    pub fn register_shortcut(&mut self, shortcut: Shortcut) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe {
+            let (raw, invoke) = shortcut.into_raw();
+            sys::UIWindowRegisterShortcut(window, raw);
+            // The window keeps the callback alive until it is destroyed
+            registry::keep(window.cast(), invoke);
+        }
+        Ok(())
+    }
+
+    /// Get a handle that other threads can use to run closures on the UI thread
+    pub fn sender(&self) -> Result<UiSender> {
+        let window = registry::live(self.raw, self.id)?;
+        Ok(unsafe { UiSender::for_window(window) })
+    }
+
+    /// Run `future` on the UI thread, polling it from the message loop
+    ///
+    /// The future is woken through this window, so it is dropped when the window is
+    /// destroyed. A panic inside it stops the loop and is resumed from [`message_loop`].
+    pub fn spawn_local(&self, future: impl Future<Output = ()> + 'static) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe { executor::spawn_local(window, future) };
+        Ok(())
+    }
+
+    /// Run `callback` once on the UI thread after `delay`
+    ///
+    /// The timer is cancelled when the window is destroyed. Timers fire from
+    /// [`message_loop`], [`pump`] and [`pump_pending`], and while a [`Dialog`] is open.
+    pub fn set_timeout(&self, delay: Duration, callback: impl FnOnce() + 'static) -> Result<Timer> {
+        let window = registry::live(self.raw, self.id)?;
+        let mut callback = Some(callback);
+        let callback = Box::new(move || {
+            if let Some(callback) = callback.take() {
+                callback();
+            }
+        });
+        Ok(unsafe { timer::start(window, delay, None, callback) })
+    }
+
+    /// Run `callback` on the UI thread every `period`
+    ///
+    /// The timer is cancelled when the window is destroyed. See
+    /// [`Window::set_timeout`] for how timers are fired.
+    pub fn set_interval(
+        &self,
+        period: Duration,
+        callback: impl FnMut() + 'static,
+    ) -> Result<Timer> {
+        let window = registry::live(self.raw, self.id)?;
+        Ok(unsafe { timer::start(window, period, Some(period), Box::new(callback)) })
+    }
+
+    /// Put `text` on the system clipboard
+    ///
+    /// On X11 the window owns the CLIPBOARD selection until another application takes it.
+    pub fn clipboard_set_text(&self, text: &str) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        let text = CString::new(text).map_err(|_| Error::InvalidString)?;
+        unsafe { sys::LuigiRsClipboardWriteText(window, text.as_ptr()) };
+        Ok(())
+    }
+
+    /// Get the text on the system clipboard, or `None` if it holds no text
+    ///
+    /// On X11 this blocks until the owning application has sent the contents.
+    pub fn clipboard_get_text(&self) -> Option<String> {
+        let window = registry::live(self.raw, self.id).ok()?;
         unsafe {
-            sys::UIWindowRegisterShortcut(self.raw, shortcut.into_raw());
+            let mut bytes = 0;
+            let text = sys::LuigiRsClipboardReadTextStart(window, &mut bytes);
+            if text.is_null() {
+                return None;
+            }
+            let contents = std::slice::from_raw_parts(text as *const u8, bytes);
+            let contents = String::from_utf8_lossy(contents).into_owned();
+            sys::LuigiRsClipboardReadTextEnd(window, text);
+            Some(contents)
         }
     }
+
+    /// Run `callback` with the paths of files dropped onto the window
+    ///
+    /// Luigi answers XDND position messages itself without forwarding them, so there
+    /// is no notification while files hover over the window, only once they land.
+    pub fn on_drop_files(&self, mut callback: impl FnMut(Vec<PathBuf>) + 'static) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        let message = sys::UIMessage_UI_MSG_WINDOW_DROP_FILES;
+        let listener = move |di, dp| {
+            if let Message::DropFiles(paths) = unsafe { Message::decode(message, di, dp) } {
+                callback(paths);
+            }
+        };
+        unsafe { registry::listen_data(window.cast(), message, Box::new(listener)) };
+        Ok(())
+    }
+
+    /// Decide what happens when the user asks to close the window
+    ///
+    /// `callback` can ask e.g. "Save changes?" before returning. A window that closes
+    /// is destroyed, and closing the last window ends [`message_loop`]. Setting a new
+    /// callback replaces the previous one.
+    pub fn on_close(&self, callback: impl FnMut() -> CloseAction + 'static) -> Result<()> {
+        registry::live(self.raw, self.id)?;
+        app::set_close_handler(self.id, Box::new(callback));
+        Ok(())
+    }
+
+    /// Run `callback` whenever the window gains the keyboard focus
+    pub fn on_activate(&self, callback: impl FnMut() + 'static) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe {
+            registry::listen(
+                window.cast(),
+                sys::UIMessage_UI_MSG_WINDOW_ACTIVATE,
+                Box::new(callback),
+            )
+        };
+        Ok(())
+    }
+
+    /// Check whether any textbox in the window was edited since the last
+    /// [`Window::reset_modified`]
+    pub fn modified(&self) -> Result<bool> {
+        let window = registry::live(self.raw, self.id)?;
+        Ok(unsafe { (*window).textboxModifiedFlag })
+    }
+
+    /// Clear the modified flag, e.g. after the document was saved
+    pub fn reset_modified(&mut self) -> Result<()> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe { (*window).textboxModifiedFlag = false };
+        Ok(())
+    }
+
+    /// Repaint the whole window and copy its framebuffer
+    ///
+    /// Fails with [`Error::NullPointer`] if the window has no framebuffer yet, which
+    /// happens on X11 until the window is first mapped.
+    pub fn capture(&self) -> Result<Image> {
+        let window = registry::live(self.raw, self.id)?;
+        unsafe {
+            sys::LuigiRsWindowCapture(window);
+            unwind::resume();
+            let (bits, width, height) = ((*window).bits, (*window).width, (*window).height);
+            if bits.is_null() || width <= 0 || height <= 0 {
+                return Err(Error::NullPointer);
+            }
+            let (width, height) = (width as usize, height as usize);
+            let pixels = std::slice::from_raw_parts(bits, width * height).to_vec();
+            Ok(Image::new(width, height, pixels))
+        }
+    }
+}
+
+/// Options for creating a [`Window`], obtained from [`Window::builder`]
+#[derive(Default)]
+pub struct WindowBuilder<'a> {
+    title: String,
+    width: i32,
+    height: i32,
+    flags: u32,
+    owner: Option<&'a Window>,
+}
+
+impl<'a> WindowBuilder<'a> {
+    /// Set the window title
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Set the size in pixels; 0 picks Luigi's default
+    pub fn size(mut self, width: i32, height: i32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set the `UI_WINDOW_*` creation flags
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Make the window owned by `owner`
+    ///
+    /// It takes the owner's scale, can be centred over it with
+    /// `UI_WINDOW_CENTER_IN_OWNER`, and is destroyed along with it.
+    pub fn owner(mut self, owner: &'a Window) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Create the window
+    ///
+    /// # Errors
+    /// [`Error::NullPointer`] if `UI_WINDOW_CENTER_IN_OWNER` is set without an owner.
+    pub fn build(self) -> Result<Window> {
+        let title = CString::new(self.title).map_err(|_| Error::InvalidString)?;
+        let owner = match self.owner {
+            Some(owner) => registry::live(owner.raw, owner.id)?,
+            None if self.flags & sys::UI_WINDOW_CENTER_IN_OWNER != 0 => {
+                return Err(Error::NullPointer)
+            }
+            None => ptr::null_mut(),
+        };
+        let raw = unsafe {
+            sys::UIWindowCreate(owner, self.flags, title.as_ptr(), self.width, self.height)
+        };
+        if raw.is_null() {
+            return Err(Error::CreateFailed);
+        }
+        let id = unsafe { registry::track(raw.cast()) };
+        unsafe { app::manage(raw, (!owner.is_null()).then_some(owner)) };
+        Ok(Window { raw, id })
+    }
+}
+
+/// What to do when the user asks to close a window, returned from [`Window::on_close`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseAction {
+    /// Let the window close
+    Close,
+    /// Keep the window open
+    Cancel,
 }
 
 // Add Element trait implementation for Window
 impl Element for Window {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e as *mut sys::UIElement }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Window {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 /// A clickable button element
 pub struct Button {
     raw: *mut sys::UIButton,
+    id: u32,
 }
 
 impl Button {
@@ -112,40 +537,73 @@ impl Button {
     /// * `label` - Text label for the button
     pub fn new(parent: &impl Element, flags: u32, label: &str) -> Result<Self> {
         let label = CString::new(label).map_err(|_| Error::InvalidString)?;
-        let raw = unsafe { sys::UIButtonCreate(parent.raw_element(), flags, label.as_ptr(), -1) };
+        let raw = unsafe { sys::UIButtonCreate(parent.live_element()?, flags, label.as_ptr(), -1) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn invoke(&self, callback: Box<dyn Fn()>) {
+    /// Set the callback run when the button is clicked
+    ///
+    /// The callback is dropped when the button is destroyed or another callback is set.
+    pub fn invoke(&self, callback: Box<dyn Fn()>) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
         unsafe {
-            let raw = self.raw;
-            // Store callback in a Box that won't be dropped
-            let callback_box = Box::new(callback);
+            registry::set_invoke(&mut (*raw).e, Rc::from(callback));
             (*raw).invoke = Some(Self::invoke_handler);
-            (*raw).e.cp = Box::into_raw(callback_box) as *mut c_void;
+            (*raw).e.cp = raw as *mut c_void;
         }
+        Ok(())
     }
 
     extern "C" fn invoke_handler(cp: *mut c_void) {
+        unsafe { registry::invoke(cp as *mut sys::UIElement) }
+    }
+
+    /// Get a stream of the button's clicks, ending when the button is destroyed
+    ///
+    /// This works alongside [`Button::invoke`] rather than replacing it.
+    pub fn clicks(&self) -> Result<Events<()>> {
+        let raw = registry::live(self.raw, self.id)?;
+        let (sink, events) = executor::events();
         unsafe {
-            let callback = &*(cp as *const Box<dyn Fn()>);
-            callback();
-        }
+            registry::listen(
+                raw.cast(),
+                sys::UIMessage_UI_MSG_CLICKED,
+                Box::new(move || sink.push(())),
+            )
+        };
+        Ok(events)
     }
 }
 
 impl Element for Button {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Button {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 /// A panel container element that can hold other elements
 pub struct Panel {
     raw: *mut sys::UIPanel,
+    id: u32,
 }
 
 impl Panel {
@@ -155,17 +613,33 @@ impl Panel {
     /// * `parent` - Parent element to attach this panel to
     /// * `flags` - Panel creation flags
     pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
-        let raw = unsafe { sys::UIPanelCreate(parent.raw_element(), flags) };
+        let raw = unsafe { sys::UIPanelCreate(parent.live_element()?, flags) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 }
 
 impl Element for Panel {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Panel {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
@@ -182,7 +656,7 @@ impl Shortcut {
     /// Create a new keyboard shortcut
     ///
     /// # Arguments
-    /// * `code` - Key code for the shortcut
+    /// * `code` - Key code for the shortcut, see [`Key::code`]
     /// * `ctrl` - Whether Control key is required
     /// * `shift` - Whether Shift key is required
     /// * `alt` - Whether Alt key is required
@@ -197,39 +671,56 @@ impl Shortcut {
         }
     }
 
-    unsafe fn into_raw(self) -> sys::UIShortcut {
+    /// Create a keyboard shortcut along with a stream of its activations
+    ///
+    /// The stream ends when the window the shortcut is registered with is destroyed.
+    pub fn events(code: i32, ctrl: bool, shift: bool, alt: bool) -> (Self, Events<()>) {
+        let (sink, events) = executor::events();
+        (
+            Self::new(code, ctrl, shift, alt, move || sink.push(())),
+            events,
+        )
+    }
+
+    /// Convert into the C representation, along with the boxed callback its `cp` points to
+    unsafe fn into_raw(self) -> (sys::UIShortcut, Box<Box<dyn Fn()>>) {
         extern "C" fn trampoline(data: *mut c_void) {
             let closure = unsafe { &*(data as *const Box<dyn Fn()>) };
-            closure();
+            unwind::catch((), closure);
         }
 
-        sys::UIShortcut {
+        let invoke = Box::new(self.invoke);
+        let raw = sys::UIShortcut {
             code: self.code,
             ctrl: self.ctrl,
             shift: self.shift,
             alt: self.alt,
             invoke: Some(trampoline),
-            cp: Box::into_raw(Box::new(self.invoke)) as *mut c_void,
-        }
+            cp: &*invoke as *const Box<dyn Fn()> as *mut c_void,
+        };
+        (raw, invoke)
     }
 }
 
 /// Initialize the Luigi UI system.
 /// Must be called before creating any windows or UI elements.
 pub fn init() {
-    unsafe {
-        sys::UIInitialise();
-        // Use an explicit font ("Arial") instead of null
-        let font_name = CString::new("Arial").unwrap();
-        let font = sys::UIFontCreate(font_name.as_ptr(), 16);
-        sys::UIFontActivate(font);
-    };
+    unsafe { sys::UIInitialise() };
+    // Luigi's own default is 11 points, replace it with the same font at 16.
+    // Without the `freetype` feature both are the bitmap font. Use `Font::load`
+    // to pick another one.
+    Font::load_default(16).activate();
 }
 
 /// Start the UI message loop.
 /// This function blocks until the application exits.
+///
+/// If a callback panics, the loop stops and the panic is resumed from here.
+/// To drive the UI from another event loop, use [`pump`] or [`pump_pending`] instead.
 pub fn message_loop() -> i32 {
-    unsafe { sys::UIMessageLoop() }
+    let result = unsafe { sys::LuigiRsMessageLoop() };
+    unwind::resume();
+    result
 }
 
 /// Create a rectangle with the given coordinates
@@ -297,6 +788,9 @@ pub fn measure_string_height() -> i32 {
 }
 
 /// Get the current animation clock value in milliseconds
+///
+/// Luigi reads this from `clock()` on Linux, so it only advances while the process
+/// uses CPU time. Use [`std::time::Instant`] to measure wall-clock durations.
 pub fn animate_clock() -> u64 {
     unsafe { sys::UIAnimateClock() }
 }
@@ -304,167 +798,155 @@ pub fn animate_clock() -> u64 {
 // Add new widget types
 pub struct Label {
     raw: *mut sys::UILabel,
+    id: u32,
 }
 
 impl Label {
     pub fn new(parent: &impl Element, flags: u32, text: &str) -> Result<Self> {
         let text = CString::new(text).map_err(|_| Error::InvalidString)?;
-        let raw = unsafe { sys::UILabelCreate(parent.raw_element(), flags, text.as_ptr(), -1) };
+        let raw = unsafe { sys::UILabelCreate(parent.live_element()?, flags, text.as_ptr(), -1) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn set_content(&self, text: &str) {
+    pub fn set_content(&self, text: &str) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
         let text = CString::new(text)
             .map_err(|_| Error::InvalidString)
             .unwrap_or_default();
         unsafe {
             // Changed -1 to text.as_bytes().len() as i32 to properly handle string length
-            sys::UILabelSetContent(self.raw, text.as_ptr(), text.as_bytes().len() as isize)
+            sys::UILabelSetContent(raw, text.as_ptr(), text.as_bytes().len() as isize)
         };
+        Ok(())
     }
 }
 
 impl Element for Label {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Label {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct Table {
     raw: *mut sys::UITable,
+    id: u32,
 }
 
 impl Table {
     pub fn new(parent: &impl Element, flags: u32, columns: &str) -> Result<Self> {
         let columns = CString::new(columns).map_err(|_| Error::InvalidString)?;
-        let raw = unsafe { sys::UITableCreate(parent.raw_element(), flags, columns.as_ptr()) };
+        let raw = unsafe { sys::UITableCreate(parent.live_element()?, flags, columns.as_ptr()) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn set_item_count(&self, count: usize) {
-        unsafe { (*self.raw).itemCount = count as i32 };
+    pub fn set_item_count(&self, count: usize) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { (*raw).itemCount = count as i32 };
+        Ok(())
     }
 
-    pub fn set_handler(&self, handler: Box<dyn EventHandler>) {
-        unsafe {
-            let raw = self.raw_element();
-            (*raw).cp = Box::into_raw(handler) as *mut c_void;
-            #[cfg(target_os = "linux")]
-            {
-                (*raw).messageUser = Some(Self::message_handler as unsafe extern "C" fn(*mut sys::UIElement, u32, i32, *mut c_void) -> i32);
-            }
-            #[cfg(not(target_os = "linux"))]
-            {
-                (*raw).messageUser = Some(Self::message_handler as unsafe extern "C" fn(*mut sys::UIElement, i32, i32, *mut c_void) -> i32);
-            }
-        }
-    }
-
-    #[cfg(target_os = "linux")]
-    extern "C" fn message_handler(
-        element: *mut sys::UIElement,
-        message: u32,
-        di: i32,
-        dp: *mut c_void,
-    ) -> i32 {
-        unsafe {
-            let handler = &*((*element).cp as *const Box<dyn EventHandler>);
-            let mut wrapper = ElementWrapper { raw: element };
-            let data = if dp.is_null() { "" } else {
-                std::ffi::CStr::from_ptr(dp as *const i8).to_str().unwrap_or("")
-            };
-            let result = handler.handle(&mut wrapper, message as i32, data);
-            if !result.is_empty() {
-                if let Some(buffer) = dp.cast::<sys::UITableGetItem>().as_mut() {
-                    let bytes = buffer.bufferBytes.min(result.len());
-                    std::ptr::copy_nonoverlapping(result.as_ptr(), buffer.buffer as *mut u8, bytes);
-                    return bytes as i32;
-                }
-            }
-            0
-        }
-    }
-
-    #[cfg(not(target_os = "linux"))]
-    extern "C" fn message_handler(
-        element: *mut sys::UIElement,
-        message: i32,
-        _di: i32,
-        dp: *mut c_void,
-    ) -> i32 {
-        unsafe {
-            let handler = &*((*element).cp as *const Box<dyn EventHandler>);
-            let mut wrapper = ElementWrapper { raw: element };
-            let data = if dp.is_null() { "" } else {
-                std::ffi::CStr::from_ptr(dp as *const i8).to_str().unwrap_or("")
-            };
-            let result = handler.handle(&mut wrapper, message, data);
-            if !result.is_empty() {
-                if let Some(buffer) = dp.cast::<sys::UITableGetItem>().as_mut() {
-                    let bytes = buffer.bufferBytes.min(result.len());
-                    std::ptr::copy_nonoverlapping(result.as_ptr(), buffer.buffer as *mut u8, bytes);
-                    return bytes as i32;
-                }
-            }
-            0
-        }
+    pub fn set_handler(&self, handler: Box<dyn EventHandler>) -> Result<()> {
+        self.on_message(
+            move |element, message| match handler.handle(element, message) {
+                0 => Response::Unhandled,
+                result => Response::Value(result),
+            },
+        )
     }
 }
 
 impl Element for Table {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
     }
-}
 
-// Helper wrapper to implement Element trait
-struct ElementWrapper {
-    raw: *mut sys::UIElement,
+    fn id(&self) -> u32 {
+        self.id
+    }
 }
 
-impl Element for ElementWrapper {
-    fn raw_element(&self) -> *mut sys::UIElement {
-        self.raw
+impl Widget for Table {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct TextBox {
     raw: *mut sys::UITextbox,
+    id: u32,
 }
 
 impl TextBox {
     pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
-        let raw = unsafe { sys::UITextboxCreate(parent.raw_element(), flags) };
+        let raw = unsafe { sys::UITextboxCreate(parent.live_element()?, flags) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn get_text(&self) -> String {
+    pub fn get_text(&self) -> Result<String> {
+        let raw = registry::live(self.raw, self.id)?;
         unsafe {
-            let text = std::slice::from_raw_parts(
-                (*self.raw).string as *const u8,
-                (*self.raw).bytes as usize,
-            );
-            String::from_utf8_lossy(text).to_string()
+            let text =
+                std::slice::from_raw_parts((*raw).string as *const u8, (*raw).bytes as usize);
+            Ok(String::from_utf8_lossy(text).to_string())
         }
     }
 
-    pub fn is_empty(&self) -> bool {
-        unsafe { (*self.raw).bytes == 0 }
+    pub fn is_empty(&self) -> Result<bool> {
+        let raw = registry::live(self.raw, self.id)?;
+        Ok(unsafe { (*raw).bytes == 0 })
     }
 }
 
 impl Element for TextBox {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for TextBox {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
@@ -472,138 +954,280 @@ impl Element for TextBox {
 
 pub struct Checkbox {
     raw: *mut sys::UICheckbox,
+    id: u32,
 }
 
 impl Checkbox {
     pub fn new(parent: &impl Element, flags: u32, label: &str) -> Result<Self> {
         let label = CString::new(label).map_err(|_| Error::InvalidString)?;
-        let raw = unsafe { sys::UICheckboxCreate(parent.raw_element(), flags, label.as_ptr(), -1) };
+        let raw =
+            unsafe { sys::UICheckboxCreate(parent.live_element()?, flags, label.as_ptr(), -1) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn get_check_state(&self) -> u8 {
-        unsafe { (*self.raw).check }
+    pub fn get_check_state(&self) -> Result<u8> {
+        let raw = registry::live(self.raw, self.id)?;
+        Ok(unsafe { (*raw).check })
     }
 }
 
 impl Element for Checkbox {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Checkbox {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct Code {
     raw: *mut sys::UICode,
+    id: u32,
 }
 
 impl Code {
     pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
-        let raw = unsafe { sys::UICodeCreate(parent.raw_element(), flags) };
+        let raw = unsafe { sys::UICodeCreate(parent.live_element()?, flags) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn insert_content(&mut self, content: &str, replace: bool) {
+    pub fn insert_content(&mut self, content: &str, replace: bool) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
         unsafe {
             sys::UICodeInsertContent(
-                self.raw,
+                raw,
                 content.as_ptr() as *const i8,
                 content.len() as isize,
                 replace,
             )
-        }
+        };
+        Ok(())
+    }
+
+    pub fn focus_line(&mut self, line: i32) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { sys::UICodeFocusLine(raw, line) };
+        Ok(())
+    }
+
+    /// Get the font the code view draws with
+    pub fn font(&self) -> Result<Option<Font>> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { Ok(Font::from_raw((*raw).font)) }
     }
 
-    pub fn focus_line(&mut self, line: i32) {
-        unsafe { sys::UICodeFocusLine(self.raw, line) }
+    /// Draw with `font` instead of the font that was active when the view was created
+    pub fn set_font(&mut self, font: Font) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe {
+            (*raw).font = font.as_raw();
+            sys::UIElementRefresh(raw.cast());
+        }
+        Ok(())
     }
 }
 
 impl Element for Code {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Code {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct Gauge {
     raw: *mut sys::UIGauge,
+    id: u32,
 }
 
 impl Gauge {
     pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
-        let raw = unsafe { sys::UIGaugeCreate(parent.raw_element(), flags) };
+        let raw = unsafe { sys::UIGaugeCreate(parent.live_element()?, flags) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
+    }
+
+    pub fn set_position(&mut self, position: f32) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { (*raw).position = position };
+        Ok(())
     }
 
-    pub fn set_position(&mut self, position: f32) {
-        unsafe { (*self.raw).position = position }
+    /// Animate the position from its current value to `to`
+    pub fn tween_position(&self, to: f32, duration: Duration, easing: Easing) -> Result<Animation> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe {
+            animation::tween_field(
+                self,
+                ptr::addr_of_mut!((*raw).position),
+                to,
+                duration,
+                easing,
+            )
+        }
     }
 }
 
 impl Element for Gauge {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Gauge {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct Slider {
     raw: *mut sys::UISlider,
+    id: u32,
 }
 
 impl Slider {
     pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
-        let raw = unsafe { sys::UISliderCreate(parent.raw_element(), flags) };
+        let raw = unsafe { sys::UISliderCreate(parent.live_element()?, flags) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn set_position(&mut self, position: f32) {
-        unsafe { (*self.raw).position = position }
+    pub fn set_position(&mut self, position: f32) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { (*raw).position = position };
+        Ok(())
     }
 
-    pub fn set_steps(&mut self, steps: i32) {
-        unsafe { (*self.raw).steps = steps }
+    /// Animate the position from its current value to `to`
+    pub fn tween_position(&self, to: f32, duration: Duration, easing: Easing) -> Result<Animation> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe {
+            animation::tween_field(
+                self,
+                ptr::addr_of_mut!((*raw).position),
+                to,
+                duration,
+                easing,
+            )
+        }
+    }
+
+    pub fn set_steps(&mut self, steps: i32) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { (*raw).steps = steps };
+        Ok(())
     }
 }
 
 impl Element for Slider {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Slider {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct MDIClient {
     raw: *mut sys::UIMDIClient,
+    id: u32,
 }
 
 impl MDIClient {
     pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
-        let raw = unsafe { sys::UIMDIClientCreate(parent.raw_element(), flags) };
+        let raw = unsafe { sys::UIMDIClientCreate(parent.live_element()?, flags) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 }
 
 impl Element for MDIClient {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for MDIClient {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct MDIChild {
     raw: *mut sys::UIMDIChild,
+    id: u32,
 }
 
 impl MDIChild {
@@ -615,99 +1239,179 @@ impl MDIChild {
     ) -> Result<Self> {
         let title = CString::new(title).map_err(|_| Error::InvalidString)?;
         let raw = unsafe {
-            sys::UIMDIChildCreate(parent.raw_element(), flags, bounds, title.as_ptr(), -1)
+            sys::UIMDIChildCreate(parent.live_element()?, flags, bounds, title.as_ptr(), -1)
         };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 }
 
 impl Element for MDIChild {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for MDIChild {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct Menu {
     raw: *mut sys::UIMenu,
+    id: u32,
 }
 
 impl Menu {
     pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
-        let raw = unsafe { sys::UIMenuCreate(parent.raw_element(), flags) };
+        let raw = unsafe { sys::UIMenuCreate(parent.live_element()?, flags) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn add_item(&mut self, flags: u32, label: &str, callback: Box<dyn Fn()>) {
+    /// Add an item to the menu
+    ///
+    /// The callback is dropped when the menu is destroyed.
+    pub fn add_item(&mut self, flags: u32, label: &str, callback: Box<dyn Fn()>) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
         let label = CString::new(label).unwrap_or_default();
         extern "C" fn trampoline(data: *mut c_void) {
             let closure = unsafe { &*(data as *const Box<dyn Fn()>) };
-            closure();
+            unwind::catch((), closure);
         }
-        let cp = Box::into_raw(callback) as *mut c_void;
+        let callback = Box::new(callback);
+        let cp = &*callback as *const Box<dyn Fn()> as *mut c_void;
         unsafe {
-            sys::UIMenuAddItem(self.raw, flags, label.as_ptr(), -1, Some(trampoline), cp);
+            sys::UIMenuAddItem(raw, flags, label.as_ptr(), -1, Some(trampoline), cp);
+            registry::keep(raw.cast(), callback);
         }
+        Ok(())
+    }
+
+    pub fn show(&self) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { sys::UIMenuShow(raw) };
+        Ok(())
     }
 
-    pub fn show(&self) {
-        unsafe { sys::UIMenuShow(self.raw) }
+    /// Show the menu and wait for an item to be picked
+    ///
+    /// Resolves to the index of the item in the order the items were added, or `None`
+    /// if the menu was dismissed. The items' own callbacks still run.
+    pub fn show_async(&self) -> Result<Choice> {
+        let raw = registry::live(self.raw, self.id)?;
+        let (sink, events) = executor::events();
+        unsafe {
+            let mut child = (*raw).e.children;
+            let mut index = 0;
+            while !child.is_null() {
+                if child != (*raw).vScroll.cast() {
+                    let sink = sink.clone();
+                    let listener = move || sink.push(index);
+                    registry::listen(child, sys::UIMessage_UI_MSG_CLICKED, Box::new(listener));
+                    index += 1;
+                }
+                child = (*child).next;
+            }
+            sys::UIMenuShow(raw);
+        }
+        Ok(Choice::new(events))
     }
 }
 
 impl Element for Menu {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Menu {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct ColorPicker {
     raw: *mut sys::UIColorPicker,
+    id: u32,
 }
 
 impl ColorPicker {
     pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
-        let raw = unsafe { sys::UIColorPickerCreate(parent.raw_element(), flags) };
+        let raw = unsafe { sys::UIColorPickerCreate(parent.live_element()?, flags) };
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn get_color(&self) -> (f32, f32, f32, f32) {
-        unsafe {
-            (
-                (*self.raw).hue,
-                (*self.raw).saturation,
-                (*self.raw).value,
-                (*self.raw).opacity,
-            )
-        }
+    pub fn get_color(&self) -> Result<(f32, f32, f32, f32)> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { Ok(((*raw).hue, (*raw).saturation, (*raw).value, (*raw).opacity)) }
     }
 
-    pub fn set_color(&mut self, h: f32, s: f32, v: f32, o: f32) {
+    pub fn set_color(&mut self, h: f32, s: f32, v: f32, o: f32) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
         unsafe {
-            (*self.raw).hue = h;
-            (*self.raw).saturation = s;
-            (*self.raw).value = v;
-            (*self.raw).opacity = o;
+            (*raw).hue = h;
+            (*raw).saturation = s;
+            (*raw).value = v;
+            (*raw).opacity = o;
         }
+        Ok(())
     }
 }
 
 impl Element for ColorPicker {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for ColorPicker {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
 
 pub struct ImageDisplay {
     raw: *mut sys::UIImageDisplay,
+    id: u32,
 }
 
 impl ImageDisplay {
@@ -720,7 +1424,7 @@ impl ImageDisplay {
     ) -> Result<Self> {
         let raw = unsafe {
             sys::UIImageDisplayCreate(
-                parent.raw_element(),
+                parent.live_element()?,
                 flags,
                 bits.as_ptr() as *mut u32, // Cast to mutable pointer
                 width,
@@ -731,24 +1435,432 @@ impl ImageDisplay {
         if raw.is_null() {
             return Err(Error::CreateFailed);
         }
-        Ok(Self { raw })
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
     }
 
-    pub fn set_content(&mut self, bits: &[u32], width: usize, height: usize) {
+    pub fn set_content(&mut self, bits: &[u32], width: usize, height: usize) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
         unsafe {
             sys::UIImageDisplaySetContent(
-                self.raw,
+                raw,
                 bits.as_ptr() as *mut u32, // Cast to mutable pointer
                 width,
                 height,
                 width * 4,
             )
-        }
+        };
+        Ok(())
     }
 }
 
 impl Element for ImageDisplay {
     fn raw_element(&self) -> *mut sys::UIElement {
-        unsafe { &mut (*self.raw).e }
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for ImageDisplay {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
+    }
+}
+
+pub struct SplitPane {
+    raw: *mut sys::UISplitPane,
+    id: u32,
+}
+
+impl SplitPane {
+    /// Create a split pane that divides its space between its first two children
+    ///
+    /// # Arguments
+    /// * `flags` - `UI_SPLIT_PANE_VERTICAL` stacks the children instead of placing them side by side
+    /// * `weight` - Fraction of the space given to the first child (0.0-1.0)
+    pub fn new(parent: &impl Element, flags: u32, weight: f32) -> Result<Self> {
+        let raw = unsafe { sys::UISplitPaneCreate(parent.live_element()?, flags, weight) };
+        if raw.is_null() {
+            return Err(Error::CreateFailed);
+        }
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
+    }
+
+    /// Get the fraction of the space given to the first child
+    pub fn weight(&self) -> Result<f32> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { Ok((*raw).weight) }
+    }
+
+    /// Set the fraction of the space given to the first child; refresh to apply it
+    pub fn set_weight(&mut self, weight: f32) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { (*raw).weight = weight.clamp(0.0, 1.0) };
+        Ok(())
+    }
+}
+
+impl Element for SplitPane {
+    fn raw_element(&self) -> *mut sys::UIElement {
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for SplitPane {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
+    }
+}
+
+pub struct TabPane {
+    raw: *mut sys::UITabPane,
+    id: u32,
+}
+
+impl TabPane {
+    /// Create a tab pane showing one child at a time
+    ///
+    /// # Arguments
+    /// * `tabs` - Tab names separated with `\t`, one per child
+    pub fn new(parent: &impl Element, flags: u32, tabs: &str) -> Result<Self> {
+        let tabs = CString::new(tabs).map_err(|_| Error::InvalidString)?;
+        let raw = unsafe { sys::UITabPaneCreate(parent.live_element()?, flags, tabs.as_ptr()) };
+        if raw.is_null() {
+            return Err(Error::CreateFailed);
+        }
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
+    }
+
+    /// Get the index of the visible tab
+    pub fn active(&self) -> Result<i32> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { Ok((*raw).active) }
+    }
+
+    /// Switch to the tab at `index`
+    pub fn set_active(&mut self, index: i32) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe {
+            (*raw).active = index;
+            sys::UIElementRefresh(raw.cast());
+        }
+        Ok(())
+    }
+
+    /// Call `callback` with the new index whenever the active tab changes
+    ///
+    /// Relayouts that keep the same tab do not call it, unlike [`Message::TabSelected`],
+    /// which the shown child receives on every layout.
+    pub fn on_tab_selected(&self, mut callback: impl FnMut(i32) + 'static) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        let mut last = unsafe { (*raw).active };
+        // Clicking a tab and `set_active` both update `active` before laying out the pane.
+        // A `set_active` from inside the callback lays out while this listener is busy,
+        // so its change is picked up by the loop rather than on some later relayout.
+        let listener = move || loop {
+            let active = unsafe { (*raw).active };
+            if active == last {
+                break;
+            }
+            last = active;
+            callback(active);
+        };
+        unsafe { registry::listen(raw.cast(), sys::UIMessage_UI_MSG_LAYOUT, Box::new(listener)) };
+        Ok(())
+    }
+}
+
+impl Element for TabPane {
+    fn raw_element(&self) -> *mut sys::UIElement {
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for TabPane {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
+    }
+}
+
+pub struct ExpandPane {
+    raw: *mut sys::UIExpandPane,
+    id: u32,
+}
+
+impl ExpandPane {
+    /// Create a pane whose children are shown or hidden by clicking its header button
+    ///
+    /// # Arguments
+    /// * `label` - Text of the header button
+    /// * `panel_flags` - Flags for the panel holding the children, e.g. `UI_PANEL_GRAY`
+    pub fn new(parent: &impl Element, flags: u32, label: &str, panel_flags: u32) -> Result<Self> {
+        let raw = unsafe {
+            sys::UIExpandPaneCreate(
+                parent.live_element()?,
+                flags,
+                label.as_ptr() as *const _,
+                label.len() as isize,
+                panel_flags,
+            )
+        };
+        if raw.is_null() {
+            return Err(Error::CreateFailed);
+        }
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
+    }
+
+    /// Check whether the children are shown
+    pub fn is_expanded(&self) -> Result<bool> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { Ok((*raw).expanded) }
+    }
+
+    /// Show or hide the children, as if the header button was clicked
+    pub fn set_expanded(&mut self, expanded: bool) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe {
+            if (*raw).expanded != expanded {
+                // The button's invoke toggles the state, updates its check mark and relayouts.
+                if let Some(invoke) = (*(*raw).button).invoke {
+                    invoke(raw.cast());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Element for ExpandPane {
+    fn raw_element(&self) -> *mut sys::UIElement {
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for ExpandPane {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
+    }
+}
+
+pub struct WrapPanel {
+    raw: *mut sys::UIWrapPanel,
+    id: u32,
+}
+
+impl WrapPanel {
+    /// Create a panel that lays out its children in rows, wrapping when a row is full
+    pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
+        let raw = unsafe { sys::UIWrapPanelCreate(parent.live_element()?, flags) };
+        if raw.is_null() {
+            return Err(Error::CreateFailed);
+        }
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
+    }
+}
+
+impl Element for WrapPanel {
+    fn raw_element(&self) -> *mut sys::UIElement {
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for WrapPanel {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
+    }
+}
+
+pub struct Spacer {
+    raw: *mut sys::UISpacer,
+    id: u32,
+}
+
+impl Spacer {
+    /// Create an empty element of a fixed size
+    ///
+    /// # Arguments
+    /// * `flags` - `UI_SPACER_LINE` draws a separator line across the spacer
+    /// * `width`, `height` - Size before scaling by the window's DPI
+    pub fn new(parent: &impl Element, flags: u32, width: i32, height: i32) -> Result<Self> {
+        let raw = unsafe { sys::UISpacerCreate(parent.live_element()?, flags, width, height) };
+        if raw.is_null() {
+            return Err(Error::CreateFailed);
+        }
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
+    }
+}
+
+impl Element for Spacer {
+    fn raw_element(&self) -> *mut sys::UIElement {
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for Spacer {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
+    }
+}
+
+pub struct ScrollBar {
+    raw: *mut sys::UIScrollBar,
+    id: u32,
+}
+
+impl ScrollBar {
+    /// Create a scroll bar; it sends [`Message::Scrolled`] to its parent when moved
+    ///
+    /// # Arguments
+    /// * `flags` - `UI_SCROLL_BAR_HORIZONTAL` for a horizontal bar
+    pub fn new(parent: &impl Element, flags: u32) -> Result<Self> {
+        let raw = unsafe { sys::UIScrollBarCreate(parent.live_element()?, flags) };
+        if raw.is_null() {
+            return Err(Error::CreateFailed);
+        }
+        Ok(Self {
+            raw,
+            id: unsafe { registry::track(raw.cast()) },
+        })
+    }
+
+    /// Get the size of the scrolled content
+    pub fn maximum(&self) -> Result<i64> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { Ok((*raw).maximum) }
+    }
+
+    /// Set the size of the scrolled content; refresh to apply it
+    pub fn set_maximum(&mut self, maximum: i64) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { (*raw).maximum = maximum };
+        Ok(())
+    }
+
+    /// Get the size of the visible part of the content
+    pub fn page(&self) -> Result<i64> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { Ok((*raw).page) }
+    }
+
+    /// Set the size of the visible part of the content; refresh to apply it
+    pub fn set_page(&mut self, page: i64) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { (*raw).page = page };
+        Ok(())
+    }
+
+    /// Get the offset of the visible part, between 0 and `maximum - page`
+    pub fn position(&self) -> Result<f64> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { Ok((*raw).position) }
+    }
+
+    /// Set the offset of the visible part; refresh to clamp and apply it
+    pub fn set_position(&mut self, position: f64) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        unsafe { (*raw).position = position };
+        Ok(())
+    }
+
+    /// Call `callback` with the new position whenever the user scrolls
+    ///
+    /// Luigi notifies the parent rather than the scroll bar, so the callback is kept
+    /// by the parent and only fires when this scroll bar's position actually changed.
+    pub fn on_scrolled(&self, mut callback: impl FnMut(f64) + 'static) -> Result<()> {
+        let raw = registry::live(self.raw, self.id)?;
+        let id = self.id;
+        let mut last = unsafe { (*raw).position };
+        let listener = move || {
+            if !registry::is_alive(raw.cast(), id) {
+                return;
+            }
+            let position = unsafe { (*raw).position };
+            if position != last {
+                last = position;
+                callback(position);
+            }
+        };
+        unsafe {
+            let parent = (*raw).e.parent;
+            registry::listen(parent, sys::UIMessage_UI_MSG_SCROLLED, Box::new(listener));
+        }
+        Ok(())
+    }
+}
+
+impl Element for ScrollBar {
+    fn raw_element(&self) -> *mut sys::UIElement {
+        self.raw.cast()
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for ScrollBar {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw: raw.cast(),
+            id,
+        }
     }
 }
@@ -0,0 +1,457 @@
+//! Typed decoding of the messages Luigi sends to elements.
+//!
+//! Every variant is matched against the `UIMessage` constants produced by bindgen,
+//! so the numbering always follows `luigi.h`.
+
+use crate::{sys, AnyElement, Element, Painter, Result};
+use std::borrow::Cow;
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+use std::path::PathBuf;
+
+// Fail the build if a message is added to or removed from `UIMessage` without
+// updating `Message`.
+const _: () = {
+    assert!(sys::UIMessage_UI_MSG_PAINT == 0);
+    assert!(sys::UIMessage_UI_MSG_USER == 33);
+};
+
+/// A message delivered to a UI element, with its `di`/`dp` payload decoded
+#[derive(Debug)]
+pub enum Message<'a> {
+    /// The element should paint itself
+    Paint(Painter<'a>),
+    /// The element should lay out its children
+    Layout,
+    /// The element is being destroyed
+    Destroy,
+    /// The hovered, pressed or focused state changed (one of the `UI_UPDATE_*` constants)
+    Update(i32),
+    /// Animation tick for an element registered with `UIElementAnimate`
+    Animate,
+    /// A scroll bar position changed
+    Scrolled,
+    /// Query the preferred width, given the height if known
+    GetWidth(i32),
+    /// Query the preferred height, given the width if known
+    GetHeight(i32),
+    /// Hit testing at a point; handle it to report the element found there
+    FindByPoint(FindByPoint<'a>),
+    /// Query the parent used for client elements
+    ClientParent(ClientParent<'a>),
+    /// Marker for the start of the input event range
+    InputEventsStart,
+    LeftDown,
+    LeftUp,
+    MiddleDown,
+    MiddleUp,
+    RightDown,
+    RightUp,
+    /// A key was typed while the element had focus
+    KeyTyped(KeyTyped<'a>),
+    MouseMove,
+    MouseDrag,
+    /// The mouse wheel moved by the given delta
+    MouseWheel(i32),
+    Clicked,
+    /// Query the cursor to show over the element
+    GetCursor,
+    /// The element or one of its descendants is being pressed
+    ///
+    /// Carries the child that is or contains the pressed element. It is `None` if the
+    /// element itself was pressed, or if the child was created by Luigi and never
+    /// wrapped (see [`AnyElement`]).
+    PressedDescendent(Option<AnyElement>),
+    /// Marker for the end of the input event range
+    InputEventsEnd,
+    /// The element's value changed
+    ValueChanged,
+    /// A table is asking for the text of a cell
+    TableGetItem(TableGetItem<'a>),
+    /// A code view is asking for the margin color of a line (starting at 1)
+    CodeGetMarginColor(i32),
+    /// A code view line is being decorated (line index starting at 1)
+    CodeDecorateLine(i32),
+    /// The window is about to close
+    WindowClose,
+    /// The tab was selected in its tab pane
+    TabSelected,
+    /// Files were dropped onto the window
+    DropFiles(Vec<PathBuf>),
+    /// The window was activated
+    WindowActivate,
+    /// An application defined message, `UI_MSG_USER + offset`
+    ///
+    /// Offsets from `0x7FFF_0000` up are reserved for the crate's own use.
+    User {
+        offset: u32,
+        di: i32,
+        dp: *mut c_void,
+    },
+    /// A message below `UI_MSG_USER` that this version of the crate does not know
+    Unknown(u32),
+}
+
+impl<'a> Message<'a> {
+    /// Decode a raw message and its data arguments
+    ///
+    /// # Safety
+    /// `di` and `dp` must be the values Luigi passed alongside `message`.
+    pub(crate) unsafe fn decode(message: sys::UIMessage, di: i32, dp: *mut c_void) -> Self {
+        match message {
+            sys::UIMessage_UI_MSG_PAINT => {
+                Message::Paint(Painter::from_raw(dp as *mut sys::UIPainter))
+            }
+            sys::UIMessage_UI_MSG_LAYOUT => Message::Layout,
+            sys::UIMessage_UI_MSG_DESTROY => Message::Destroy,
+            sys::UIMessage_UI_MSG_UPDATE => Message::Update(di),
+            sys::UIMessage_UI_MSG_ANIMATE => Message::Animate,
+            sys::UIMessage_UI_MSG_SCROLLED => Message::Scrolled,
+            sys::UIMessage_UI_MSG_GET_WIDTH => Message::GetWidth(di),
+            sys::UIMessage_UI_MSG_GET_HEIGHT => Message::GetHeight(di),
+            sys::UIMessage_UI_MSG_FIND_BY_POINT => Message::FindByPoint(FindByPoint {
+                raw: &mut *(dp as *mut sys::UIFindByPoint),
+            }),
+            sys::UIMessage_UI_MSG_CLIENT_PARENT => Message::ClientParent(ClientParent {
+                raw: &mut *(dp as *mut *mut sys::UIElement),
+            }),
+            sys::UIMessage_UI_MSG_INPUT_EVENTS_START => Message::InputEventsStart,
+            sys::UIMessage_UI_MSG_LEFT_DOWN => Message::LeftDown,
+            sys::UIMessage_UI_MSG_LEFT_UP => Message::LeftUp,
+            sys::UIMessage_UI_MSG_MIDDLE_DOWN => Message::MiddleDown,
+            sys::UIMessage_UI_MSG_MIDDLE_UP => Message::MiddleUp,
+            sys::UIMessage_UI_MSG_RIGHT_DOWN => Message::RightDown,
+            sys::UIMessage_UI_MSG_RIGHT_UP => Message::RightUp,
+            sys::UIMessage_UI_MSG_KEY_TYPED => {
+                Message::KeyTyped(KeyTyped::from_raw(&*(dp as *const sys::UIKeyTyped)))
+            }
+            sys::UIMessage_UI_MSG_MOUSE_MOVE => Message::MouseMove,
+            sys::UIMessage_UI_MSG_MOUSE_DRAG => Message::MouseDrag,
+            sys::UIMessage_UI_MSG_MOUSE_WHEEL => Message::MouseWheel(di),
+            sys::UIMessage_UI_MSG_CLICKED => Message::Clicked,
+            sys::UIMessage_UI_MSG_GET_CURSOR => Message::GetCursor,
+            sys::UIMessage_UI_MSG_PRESSED_DESCENDENT => {
+                let child = dp as *mut sys::UIElement;
+                Message::PressedDescendent(if child.is_null() {
+                    None
+                } else {
+                    AnyElement::wrap(child)
+                })
+            }
+            sys::UIMessage_UI_MSG_INPUT_EVENTS_END => Message::InputEventsEnd,
+            sys::UIMessage_UI_MSG_VALUE_CHANGED => Message::ValueChanged,
+            sys::UIMessage_UI_MSG_TABLE_GET_ITEM => Message::TableGetItem(TableGetItem {
+                raw: &mut *(dp as *mut sys::UITableGetItem),
+            }),
+            sys::UIMessage_UI_MSG_CODE_GET_MARGIN_COLOR => Message::CodeGetMarginColor(di),
+            sys::UIMessage_UI_MSG_CODE_DECORATE_LINE => {
+                Message::CodeDecorateLine((*(dp as *const sys::UICodeDecorateLine)).index)
+            }
+            sys::UIMessage_UI_MSG_WINDOW_CLOSE => Message::WindowClose,
+            sys::UIMessage_UI_MSG_TAB_SELECTED => Message::TabSelected,
+            sys::UIMessage_UI_MSG_WINDOW_DROP_FILES => {
+                Message::DropFiles(paths_from_raw(di, dp as *const *const c_char))
+            }
+            sys::UIMessage_UI_MSG_WINDOW_ACTIVATE => Message::WindowActivate,
+            message if message >= sys::UIMessage_UI_MSG_USER => Message::User {
+                offset: user_offset(message),
+                di,
+                dp,
+            },
+            message => Message::Unknown(number(message)),
+        }
+    }
+}
+
+/// Get the raw `UIMessage` value for an application defined message
+#[allow(clippy::unnecessary_cast)]
+pub(crate) fn user_message(offset: u32) -> sys::UIMessage {
+    sys::UIMessage_UI_MSG_USER + offset as sys::UIMessage
+}
+
+// Only called for messages from `UI_MSG_USER` up, so it cannot underflow.
+fn user_offset(message: sys::UIMessage) -> u32 {
+    number(message - sys::UIMessage_UI_MSG_USER)
+}
+
+// `UIMessage` is unsigned with GCC/Clang but signed with MSVC.
+#[allow(clippy::unnecessary_cast)]
+fn number(message: sys::UIMessage) -> u32 {
+    message as u32
+}
+
+unsafe fn paths_from_raw(count: i32, paths: *const *const c_char) -> Vec<PathBuf> {
+    if paths.is_null() {
+        return Vec::new();
+    }
+    (0..count.max(0) as usize)
+        .map(|i| path_from_c(CStr::from_ptr(*paths.add(i))))
+        .collect()
+}
+
+#[cfg(unix)]
+fn path_from_c(path: &CStr) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(path.to_bytes()))
+}
+
+#[cfg(not(unix))]
+fn path_from_c(path: &CStr) -> PathBuf {
+    PathBuf::from(path.to_string_lossy().into_owned())
+}
+
+/// Payload of [`Message::KeyTyped`]
+#[derive(Debug)]
+pub struct KeyTyped<'a> {
+    /// Text produced by the key press, empty for non-character keys
+    pub text: Cow<'a, str>,
+    /// Platform key code, compare against [`Key::code`]
+    pub code: isize,
+}
+
+impl<'a> KeyTyped<'a> {
+    unsafe fn from_raw(raw: &'a sys::UIKeyTyped) -> Self {
+        let text = if raw.text.is_null() || raw.textBytes <= 0 {
+            &[][..]
+        } else {
+            std::slice::from_raw_parts(raw.text as *const u8, raw.textBytes as usize)
+        };
+        KeyTyped {
+            text: String::from_utf8_lossy(text),
+            code: raw.code,
+        }
+    }
+}
+
+/// A key, for comparing against [`KeyTyped::code`] or synthesizing input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Enter,
+    Escape,
+    Tab,
+    Space,
+    Backspace,
+    Delete,
+    Insert,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    /// A letter key, `'A'` to `'Z'` in either case
+    Letter(char),
+    /// A digit key, `'0'` to `'9'`
+    Digit(char),
+    /// A function key, starting at 1
+    F(u8),
+}
+
+impl Key {
+    /// Get the platform key code, one of the `UI_KEYCODE_*` values
+    pub fn code(self) -> i32 {
+        unsafe {
+            match self {
+                Key::Enter => sys::UI_KEYCODE_ENTER,
+                Key::Escape => sys::UI_KEYCODE_ESCAPE,
+                Key::Tab => sys::UI_KEYCODE_TAB,
+                Key::Space => sys::UI_KEYCODE_SPACE,
+                Key::Backspace => sys::UI_KEYCODE_BACKSPACE,
+                Key::Delete => sys::UI_KEYCODE_DELETE,
+                Key::Insert => sys::UI_KEYCODE_INSERT,
+                Key::Up => sys::UI_KEYCODE_UP,
+                Key::Down => sys::UI_KEYCODE_DOWN,
+                Key::Left => sys::UI_KEYCODE_LEFT,
+                Key::Right => sys::UI_KEYCODE_RIGHT,
+                Key::Home => sys::UI_KEYCODE_HOME,
+                Key::End => sys::UI_KEYCODE_END,
+                Key::Letter(c) => sys::UI_KEYCODE_A + (c.to_ascii_uppercase() as i32 - 'A' as i32),
+                Key::Digit(c) => sys::UI_KEYCODE_0 + (c as i32 - '0' as i32),
+                Key::F(n) => sys::UI_KEYCODE_F1 + (n as i32 - 1),
+            }
+        }
+    }
+
+    /// The text the key types, if any
+    #[cfg_attr(not(feature = "headless"), allow(dead_code))]
+    pub(crate) fn text(self) -> Option<char> {
+        match self {
+            Key::Space => Some(' '),
+            Key::Letter(c) | Key::Digit(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// The key that types `c`, if there is one
+    #[cfg_attr(not(feature = "headless"), allow(dead_code))]
+    pub(crate) fn for_char(c: char) -> Option<Key> {
+        match c {
+            '\n' => Some(Key::Enter),
+            '\t' => Some(Key::Tab),
+            ' ' => Some(Key::Space),
+            c if c.is_ascii_alphabetic() => Some(Key::Letter(c)),
+            c if c.is_ascii_digit() => Some(Key::Digit(c)),
+            _ => None,
+        }
+    }
+}
+
+/// Payload of [`Message::FindByPoint`]
+#[derive(Debug)]
+pub struct FindByPoint<'a> {
+    raw: &'a mut sys::UIFindByPoint,
+}
+
+impl FindByPoint<'_> {
+    /// Horizontal position of the point, in window coordinates
+    pub fn x(&self) -> i32 {
+        self.raw.x
+    }
+
+    /// Vertical position of the point, in window coordinates
+    pub fn y(&self) -> i32 {
+        self.raw.y
+    }
+
+    /// Report `element` as the one at the point
+    ///
+    /// The handler should then return [`crate::Response::Handled`], or Luigi keeps
+    /// searching the children itself.
+    pub fn set_result(&mut self, element: &impl Element) -> Result<()> {
+        self.raw.result = element.live_element()?;
+        Ok(())
+    }
+}
+
+/// Payload of [`Message::ClientParent`]
+#[derive(Debug)]
+pub struct ClientParent<'a> {
+    raw: &'a mut *mut sys::UIElement,
+}
+
+impl ClientParent<'_> {
+    /// Create the new child inside `parent` instead of the element that got the message
+    pub fn set(&mut self, parent: &impl Element) -> Result<()> {
+        *self.raw = parent.live_element()?;
+        Ok(())
+    }
+}
+
+/// Payload of [`Message::TableGetItem`]
+#[derive(Debug)]
+pub struct TableGetItem<'a> {
+    raw: &'a mut sys::UITableGetItem,
+}
+
+impl TableGetItem<'_> {
+    /// Row being requested
+    pub fn index(&self) -> i32 {
+        self.raw.index
+    }
+
+    /// Column being requested
+    pub fn column(&self) -> i32 {
+        self.raw.column
+    }
+
+    /// Whether the row is currently selected
+    pub fn is_selected(&self) -> bool {
+        self.raw.isSelected
+    }
+
+    /// Mark the row as selected or not
+    pub fn set_selected(&mut self, selected: bool) {
+        self.raw.isSelected = selected;
+    }
+
+    /// Copy the cell text into the table's buffer
+    ///
+    /// Returns the number of bytes written, which the handler should return to Luigi.
+    pub fn write(&mut self, text: &str) -> i32 {
+        let bytes = self.raw.bufferBytes.min(text.len());
+        unsafe { std::ptr::copy_nonoverlapping(text.as_ptr(), self.raw.buffer as *mut u8, bytes) };
+        bytes as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::ptr;
+
+    #[test]
+    fn user_offsets_round_trip() {
+        for offset in [0, 1, 0x1234, 0x7FFF_0000] {
+            let message = unsafe { Message::decode(user_message(offset), -3, ptr::null_mut()) };
+            match message {
+                Message::User { offset: o, di, dp } => {
+                    assert_eq!((o, di), (offset, -3));
+                    assert!(dp.is_null());
+                }
+                other => panic!("{other:?} decoded for offset {offset}"),
+            }
+        }
+    }
+
+    #[test]
+    fn payloads_from_di() {
+        let decode = |message, di| unsafe { Message::decode(message, di, ptr::null_mut()) };
+        assert!(matches!(
+            decode(sys::UIMessage_UI_MSG_MOUSE_WHEEL, -120),
+            Message::MouseWheel(-120)
+        ));
+        assert!(matches!(
+            decode(sys::UIMessage_UI_MSG_GET_WIDTH, 40),
+            Message::GetWidth(40)
+        ));
+        assert!(matches!(
+            decode(sys::UIMessage_UI_MSG_WINDOW_ACTIVATE, 0),
+            Message::WindowActivate
+        ));
+        assert!(matches!(
+            decode(sys::UIMessage_UI_MSG_PRESSED_DESCENDENT, 0),
+            Message::PressedDescendent(None)
+        ));
+    }
+
+    #[test]
+    fn find_by_point() {
+        let mut raw = sys::UIFindByPoint {
+            x: 12,
+            y: 34,
+            result: ptr::null_mut(),
+        };
+        let message = unsafe {
+            Message::decode(
+                sys::UIMessage_UI_MSG_FIND_BY_POINT,
+                0,
+                ptr::addr_of_mut!(raw).cast(),
+            )
+        };
+        let Message::FindByPoint(find) = message else {
+            panic!("{message:?}");
+        };
+        assert_eq!((find.x(), find.y()), (12, 34));
+    }
+
+    #[test]
+    fn dropped_files() {
+        let files = [
+            CString::new("/tmp/a.txt").unwrap(),
+            CString::new("b").unwrap(),
+        ];
+        let mut paths = files.iter().map(|file| file.as_ptr()).collect::<Vec<_>>();
+        let message = unsafe {
+            Message::decode(
+                sys::UIMessage_UI_MSG_WINDOW_DROP_FILES,
+                2,
+                paths.as_mut_ptr().cast(),
+            )
+        };
+        let Message::DropFiles(paths) = message else {
+            panic!("{message:?}");
+        };
+        assert_eq!(paths, [PathBuf::from("/tmp/a.txt"), PathBuf::from("b")]);
+    }
+}
@@ -0,0 +1,289 @@
+//! Safe drawing on top of the `UIDraw*` family.
+
+use crate::sys;
+
+/// A rectangle given by its left, right, top and bottom edges
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub l: i32,
+    pub r: i32,
+    pub t: i32,
+    pub b: i32,
+}
+
+impl Rect {
+    /// Create a rectangle from its edges
+    pub fn new(l: i32, r: i32, t: i32, b: i32) -> Self {
+        Self { l, r, t, b }
+    }
+
+    /// Create a rectangle from its top-left corner and size
+    pub fn from_size(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self::new(x, x + width, y, y + height)
+    }
+
+    pub fn width(&self) -> i32 {
+        self.r - self.l
+    }
+
+    pub fn height(&self) -> i32 {
+        self.b - self.t
+    }
+
+    /// Whether the rectangle covers any pixels
+    pub fn is_valid(&self) -> bool {
+        self.width() > 0 && self.height() > 0
+    }
+
+    /// Whether the point lies inside the rectangle
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        unsafe { sys::UIRectangleContains((*self).into(), x, y) }
+    }
+
+    /// Get the overlap of two rectangles
+    pub fn intersection(&self, other: Rect) -> Rect {
+        unsafe { sys::UIRectangleIntersection((*self).into(), other.into()).into() }
+    }
+
+    /// Get the smallest rectangle containing both rectangles
+    pub fn bounding(&self, other: Rect) -> Rect {
+        unsafe { sys::UIRectangleBounding((*self).into(), other.into()).into() }
+    }
+
+    /// Add the edges of two rectangles, e.g. to inset by a border
+    pub fn add(&self, other: Rect) -> Rect {
+        unsafe { sys::UIRectangleAdd((*self).into(), other.into()).into() }
+    }
+}
+
+impl From<sys::UIRectangle> for Rect {
+    fn from(rect: sys::UIRectangle) -> Self {
+        Self::new(rect.l, rect.r, rect.t, rect.b)
+    }
+}
+
+impl From<Rect> for sys::UIRectangle {
+    fn from(rect: Rect) -> Self {
+        sys::UIRectangle {
+            l: rect.l,
+            r: rect.r,
+            t: rect.t,
+            b: rect.b,
+        }
+    }
+}
+
+/// A color in Luigi's `0xAARRGGBB` format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Color(pub u32);
+
+impl Color {
+    pub const BLACK: Color = Color(0xFF000000);
+    pub const WHITE: Color = Color(0xFFFFFFFF);
+
+    /// Create an opaque color from its channels
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, 0xFF)
+    }
+
+    /// Create a color from its channels, including alpha
+    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color((a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32)
+    }
+
+    /// Create an opaque color from HSV values (each 0.0-1.0)
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+        Color(crate::color_to_rgb(h, s, v) | 0xFF000000)
+    }
+
+    /// Convert to HSV values, see [`crate::color_to_hsv`]
+    pub fn to_hsv(self) -> Option<(f32, f32, f32)> {
+        crate::color_to_hsv(self.0)
+    }
+
+    pub fn red(self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    pub fn green(self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    pub fn blue(self) -> u8 {
+        self.0 as u8
+    }
+
+    pub fn alpha(self) -> u8 {
+        (self.0 >> 24) as u8
+    }
+}
+
+impl From<u32> for Color {
+    fn from(color: u32) -> Self {
+        Color(color)
+    }
+}
+
+impl From<Color> for u32 {
+    fn from(color: Color) -> Self {
+        color.0
+    }
+}
+
+/// Selection and caret drawn by [`Painter::string`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StringSelection {
+    /// Byte offsets of the selection; the caret is drawn at `carets[0]`
+    pub carets: [i32; 2],
+    pub color_text: Color,
+    pub color_background: Color,
+}
+
+impl From<StringSelection> for sys::UIStringSelection {
+    fn from(selection: StringSelection) -> Self {
+        sys::UIStringSelection {
+            carets: selection.carets,
+            colorText: selection.color_text.0,
+            colorBackground: selection.color_background.0,
+        }
+    }
+}
+
+/// Draws into a window's framebuffer, limited to a clip rectangle
+///
+/// Painters are handed out with [`crate::Message::Paint`]; the clip starts as the
+/// visible part of the element being painted.
+#[derive(Debug)]
+pub struct Painter<'a> {
+    raw: &'a mut sys::UIPainter,
+}
+
+impl<'a> Painter<'a> {
+    /// # Safety
+    /// `raw` must be the painter Luigi passed with `UI_MSG_PAINT`.
+    pub(crate) unsafe fn from_raw(raw: *mut sys::UIPainter) -> Self {
+        Self { raw: &mut *raw }
+    }
+
+    /// Get the rectangle drawing is currently limited to
+    pub fn clip(&self) -> Rect {
+        self.raw.clip.into()
+    }
+
+    /// Width of the framebuffer in pixels
+    pub fn width(&self) -> i32 {
+        self.raw.width
+    }
+
+    /// Height of the framebuffer in pixels
+    pub fn height(&self) -> i32 {
+        self.raw.height
+    }
+
+    /// Run `f` with the clip narrowed to `rect`, restoring it afterwards
+    ///
+    /// The clip can only shrink, so drawing never escapes the element being painted.
+    pub fn with_clip<R>(&mut self, rect: Rect, f: impl FnOnce(&mut Painter) -> R) -> R {
+        let old = self.raw.clip;
+        self.raw.clip = self.clip().intersection(rect).into();
+        let result = f(self);
+        self.raw.clip = old;
+        result
+    }
+
+    /// Fill a rectangle with a solid color
+    pub fn block(&mut self, rect: Rect, color: Color) {
+        unsafe { sys::UIDrawBlock(self.raw, rect.into(), color.0) }
+    }
+
+    /// Invert the colors of a rectangle
+    pub fn invert(&mut self, rect: Rect) {
+        unsafe { sys::UIDrawInvert(self.raw, rect.into()) }
+    }
+
+    /// Draw a line, returning false if none of it was visible
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) -> bool {
+        unsafe { sys::UIDrawLine(self.raw, x0, y0, x1, y1, color.0) }
+    }
+
+    /// Fill a triangle
+    pub fn triangle(&mut self, points: [(i32, i32); 3], color: Color) {
+        let [(x0, y0), (x1, y1), (x2, y2)] = points;
+        unsafe { sys::UIDrawTriangle(self.raw, x0, y0, x1, y1, x2, y2, color.0) }
+    }
+
+    /// Draw the outline of a triangle
+    pub fn triangle_outline(&mut self, points: [(i32, i32); 3], color: Color) {
+        let [(x0, y0), (x1, y1), (x2, y2)] = points;
+        unsafe { sys::UIDrawTriangleOutline(self.raw, x0, y0, x1, y1, x2, y2, color.0) }
+    }
+
+    /// Draw a single glyph of the active font with its top-left corner at (x, y)
+    ///
+    /// Characters outside ASCII are drawn as `?`.
+    pub fn glyph(&mut self, x: i32, y: i32, c: char, color: Color) {
+        let c = if c.is_ascii() { c as i32 } else { '?' as i32 };
+        unsafe { sys::UIDrawGlyph(self.raw, x, y, c, color.0) }
+    }
+
+    /// Fill a rectangle and draw a border inside it
+    ///
+    /// # Arguments
+    /// * `border_size` - Border width on each edge
+    pub fn rectangle(&mut self, rect: Rect, main: Color, border: Color, border_size: Rect) {
+        unsafe { sys::UIDrawRectangle(self.raw, rect.into(), main.0, border.0, border_size.into()) }
+    }
+
+    /// Draw a border inside a rectangle
+    ///
+    /// # Arguments
+    /// * `border_size` - Border width on each edge
+    pub fn border(&mut self, rect: Rect, color: Color, border_size: Rect) {
+        unsafe { sys::UIDrawBorder(self.raw, rect.into(), color.0, border_size.into()) }
+    }
+
+    /// Draw a line of text, vertically centered in `rect`
+    ///
+    /// # Arguments
+    /// * `align` - One of `UI_ALIGN_LEFT`, `UI_ALIGN_RIGHT` or `UI_ALIGN_CENTER`
+    /// * `selection` - Optional selection and caret to draw
+    pub fn string(
+        &mut self,
+        rect: Rect,
+        text: &str,
+        color: Color,
+        align: u32,
+        selection: Option<&StringSelection>,
+    ) {
+        let mut selection = selection.map(|&s| sys::UIStringSelection::from(s));
+        let selection = selection
+            .as_mut()
+            .map_or(std::ptr::null_mut(), |s| s as *mut _);
+        unsafe {
+            sys::UIDrawString(
+                self.raw,
+                rect.into(),
+                text.as_ptr() as *const _,
+                text.len() as isize,
+                color.0,
+                align as i32,
+                selection,
+            )
+        }
+    }
+
+    /// Draw a line of text with the code view's syntax highlighting
+    ///
+    /// Returns the x coordinate after the last character.
+    pub fn string_highlighted(&mut self, rect: Rect, text: &str, tab_size: i32) -> i32 {
+        unsafe {
+            sys::UIDrawStringHighlighted(
+                self.raw,
+                rect.into(),
+                text.as_ptr() as *const _,
+                text.len() as isize,
+                tab_size,
+            )
+        }
+    }
+}
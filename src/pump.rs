@@ -0,0 +1,67 @@
+//! Driving the message loop one step at a time from an external event loop.
+
+use crate::{sys, timer, unwind};
+use std::time::Duration;
+
+/// Result of a single step of the message loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pump {
+    /// The UI is still running
+    Running,
+    /// The application quit, with the code [`crate::message_loop`] would have returned
+    Exited(i32),
+}
+
+/// Wait for the next batch of events or the next timer, and handle it
+///
+/// While an element is animating this runs one animation step instead of waiting.
+/// Use this or [`pump_pending`] instead of [`crate::message_loop`], not together with it.
+///
+/// If a callback panics, the panic is resumed from here.
+pub fn pump() -> Pump {
+    step(false)
+}
+
+/// Handle every event that has already arrived, without waiting
+///
+/// Afterwards the connection is drained, so it is safe to wait for
+/// [`connection_fd`] to become readable, for at most [`next_timer`]. While
+/// [`is_animating`] is true, keep calling this at the frame rate instead.
+pub fn pump_pending() -> Pump {
+    step(true)
+}
+
+fn step(only_pending: bool) -> Pump {
+    let mut result = 0;
+    let running = unsafe { sys::LuigiRsMessageLoopSingle(only_pending, &mut result) };
+    unwind::resume();
+    if running {
+        Pump::Running
+    } else {
+        Pump::Exited(result)
+    }
+}
+
+/// Check whether any element is animating and needs regular pumping
+pub fn is_animating() -> bool {
+    unsafe { sys::LuigiRsIsAnimating() }
+}
+
+/// Get the time left until the next timer from [`crate::Window::set_timeout`] or
+/// [`crate::Window::set_interval`] is due
+///
+/// An external loop should wait for events no longer than this before calling
+/// [`pump_pending`] again. Returns `None` if no timer is pending.
+pub fn next_timer() -> Option<Duration> {
+    timer::next_due()
+}
+
+/// Get the file descriptor of the X11 connection
+///
+/// It becomes readable when new events arrive, for use with `poll`, `epoll` or an async
+/// reactor. Returns `None` before [`crate::init`] is called.
+#[cfg(unix)]
+pub fn connection_fd() -> Option<std::os::unix::io::RawFd> {
+    let fd = unsafe { sys::LuigiRsConnectionNumber() };
+    (fd >= 0).then_some(fd)
+}
@@ -0,0 +1,273 @@
+//! Rust-side state attached to Luigi elements.
+//!
+//! Luigi elements only have a single `messageUser` slot, so every Rust handler goes
+//! through one trampoline that looks up the element's state by its `UIElement.id`.
+//! The state, including any closures the element owns, is dropped on `UI_MSG_DESTROY`.
+
+use crate::{sys, unwind, AnyElement, Element, Error, Message, Result, Widget};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+/// Value returned from an element message handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response {
+    /// Let the element's default behaviour run
+    Unhandled,
+    /// The message was handled, skip the default behaviour
+    Handled,
+    /// The message was handled and produced a value, e.g. a width or a string length
+    Value(i32),
+}
+
+impl Response {
+    pub(crate) fn into_raw(self) -> i32 {
+        match self {
+            Response::Unhandled => 0,
+            Response::Handled => 1,
+            Response::Value(value) => value,
+        }
+    }
+}
+
+pub(crate) type MessageHandler = dyn FnMut(&mut dyn Element, Message) -> Response;
+
+type Listener = Rc<RefCell<Box<dyn FnMut(i32, *mut c_void) -> i32>>>;
+
+type MessageFn = unsafe extern "C" fn(*mut sys::UIElement, sys::UIMessage, i32, *mut c_void) -> i32;
+
+#[derive(Default)]
+struct ElementState {
+    handler: Option<Rc<RefCell<Box<MessageHandler>>>>,
+    previous: Option<MessageFn>,
+    invoke: Option<Rc<dyn Fn()>>,
+    listeners: Vec<(sys::UIMessage, Listener)>,
+    owned: Vec<Box<dyn Any>>,
+}
+
+thread_local! {
+    static STATES: RefCell<HashMap<u32, ElementState>> = RefCell::new(HashMap::new());
+}
+
+/// Start tracking an element so its liveness can be checked, returning its id
+pub(crate) unsafe fn track(element: *mut sys::UIElement) -> u32 {
+    with_state(element, |_| ());
+    (*element).id
+}
+
+/// Check whether a tracked element still exists
+///
+/// Ids are never reused by Luigi, so a stale pointer is never mistaken for a new element.
+pub(crate) fn is_alive(element: *mut sys::UIElement, id: u32) -> bool {
+    STATES.with(|states| states.borrow().contains_key(&id))
+        && unsafe { (*element).flags & sys::UI_ELEMENT_DESTROY == 0 }
+}
+
+/// Get the pointer back if the element still exists
+pub(crate) fn live<T>(element: *mut T, id: u32) -> Result<*mut T> {
+    if is_alive(element.cast(), id) {
+        Ok(element)
+    } else {
+        Err(Error::Destroyed)
+    }
+}
+
+unsafe fn with_state<R>(element: *mut sys::UIElement, f: impl FnOnce(&mut ElementState) -> R) -> R {
+    STATES.with(|states| {
+        let mut states = states.borrow_mut();
+        let state = states.entry((*element).id).or_insert_with(|| ElementState {
+            // Chain to a hook Luigi installed itself, such as the one on menu items.
+            previous: (*element).messageUser.replace(trampoline),
+            ..Default::default()
+        });
+        f(state)
+    })
+}
+
+/// Attach a message handler to an element, replacing any previous Rust handler
+pub(crate) unsafe fn set_handler(element: *mut sys::UIElement, handler: Box<MessageHandler>) {
+    let handler = Rc::new(RefCell::new(handler));
+    let old = with_state(element, |state| state.handler.replace(handler));
+    drop(old);
+}
+
+/// Set the callback run by [`invoke`], replacing any previous one
+pub(crate) unsafe fn set_invoke(element: *mut sys::UIElement, invoke: Rc<dyn Fn()>) {
+    let old = with_state(element, |state| state.invoke.replace(invoke));
+    drop(old);
+}
+
+/// Run the callback set with [`set_invoke`], if the element is still alive
+pub(crate) unsafe fn invoke(element: *mut sys::UIElement) {
+    let id = (*element).id;
+    let invoke = STATES.with(|states| {
+        states
+            .borrow()
+            .get(&id)
+            .and_then(|state| state.invoke.clone())
+    });
+    if let Some(invoke) = invoke {
+        unwind::catch((), || invoke());
+    }
+}
+
+/// Run `listener` whenever the element receives `message`
+///
+/// Listeners run before the message handler and do not change the result, so wrappers
+/// can watch notifications without taking the element's single handler slot.
+pub(crate) unsafe fn listen(
+    element: *mut sys::UIElement,
+    message: sys::UIMessage,
+    mut listener: Box<dyn FnMut()>,
+) {
+    listen_data(element, message, Box::new(move |_, _| listener()));
+}
+
+/// Like [`listen`], but the listener also gets the message's `di` and `dp`
+pub(crate) unsafe fn listen_data(
+    element: *mut sys::UIElement,
+    message: sys::UIMessage,
+    mut listener: Box<dyn FnMut(i32, *mut c_void)>,
+) {
+    intercept(
+        element,
+        message,
+        Box::new(move |di, dp| {
+            listener(di, dp);
+            0
+        }),
+    );
+}
+
+/// Like [`listen_data`], but a non-zero return value becomes the message's result
+///
+/// The message handler and the element's own message class are then skipped, so
+/// wrappers can override a default such as closing the window.
+pub(crate) unsafe fn intercept(
+    element: *mut sys::UIElement,
+    message: sys::UIMessage,
+    listener: Box<dyn FnMut(i32, *mut c_void) -> i32>,
+) {
+    let listener = Rc::new(RefCell::new(listener));
+    with_state(element, |state| state.listeners.push((message, listener)));
+}
+
+/// Keep `value` alive until the element is destroyed
+pub(crate) unsafe fn keep(element: *mut sys::UIElement, value: Box<dyn Any>) {
+    with_state(element, |state| state.owned.push(value));
+}
+
+unsafe extern "C" fn trampoline(
+    element: *mut sys::UIElement,
+    message: sys::UIMessage,
+    di: i32,
+    dp: *mut c_void,
+) -> i32 {
+    let id = (*element).id;
+    let (handler, previous, listeners) = STATES.with(|states| {
+        states
+            .borrow()
+            .get(&id)
+            .map(|state| {
+                let listeners = state
+                    .listeners
+                    .iter()
+                    .filter(|(m, _)| *m == message)
+                    .map(|(_, listener)| listener.clone())
+                    .collect::<Vec<_>>();
+                (state.handler.clone(), state.previous, listeners)
+            })
+            .unwrap_or_default()
+    });
+
+    let mut result = 0;
+    for listener in &listeners {
+        if let Ok(mut listener) = listener.try_borrow_mut() {
+            let intercepted = unwind::catch(0, || listener(di, dp));
+            if result == 0 {
+                result = intercepted;
+            }
+        }
+    }
+
+    // A handler that re-enters its own element (e.g. by refreshing it) falls back to the defaults.
+    if result == 0 {
+        if let Some(mut handler) = handler.as_ref().and_then(|h| h.try_borrow_mut().ok()) {
+            let mut wrapper = AnyElement::from_raw(element, id);
+            result = unwind::catch(0, || {
+                let message = Message::decode(message, di, dp);
+                handler(&mut wrapper, message).into_raw()
+            });
+        }
+    }
+    if result == 0 {
+        if let Some(previous) = previous {
+            result = previous(element, message, di, dp);
+        }
+    }
+
+    if message == sys::UIMessage_UI_MSG_DESTROY {
+        // Dropped after the borrow above ends, in case the state owns the last reference.
+        let state = STATES.with(|states| states.borrow_mut().remove(&id));
+        unwind::catch((), || {
+            drop(handler);
+            drop(listeners);
+            drop(state);
+        });
+    }
+    result
+}
+
+/// A weak reference to an element that can be upgraded back into its typed wrapper
+///
+/// Handles can be moved to other threads, e.g. into closures for [`crate::UiSender`],
+/// but only upgrade on the UI thread.
+pub struct Handle<T> {
+    raw: *mut sys::UIElement,
+    id: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Widget> Handle<T> {
+    pub(crate) unsafe fn new(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self {
+            raw,
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Check whether the element still exists
+    pub fn is_alive(&self) -> bool {
+        is_alive(self.raw, self.id)
+    }
+
+    /// Get the typed wrapper back, or [`Error::Destroyed`] if the element is gone
+    pub fn upgrade(&self) -> Result<T> {
+        let raw = live(self.raw, self.id)?;
+        Ok(unsafe { T::from_raw(raw, self.id) })
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+// The pointer is only dereferenced once the id is found in the thread-local registry,
+// so on any other thread a handle simply reports the element as gone.
+unsafe impl<T> Send for Handle<T> {}
+unsafe impl<T> Sync for Handle<T> {}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle").field("id", &self.id).finish()
+    }
+}
@@ -0,0 +1,136 @@
+//! Running closures on the UI thread from other threads.
+
+use crate::{message, registry, sys, Error, Result};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Offset of the user message that wakes a window to run queued closures
+pub(crate) const RUN_QUEUED: u32 = 0x7FFF_0000;
+
+type Task = Box<dyn FnOnce() + Send>;
+
+struct WindowPtr(*mut sys::UIWindow);
+
+// The pointer is only passed to `UIWindowPostMessage`, which is thread-safe, and is
+// cleared on the UI thread before the window is freed.
+unsafe impl Send for WindowPtr {}
+
+struct Shared {
+    window: Mutex<Option<WindowPtr>>,
+    queue: Mutex<VecDeque<Task>>,
+}
+
+impl Shared {
+    fn run_queued(&self) {
+        loop {
+            // Not held while running, so closures can send more work.
+            let task = self.lock_queue().pop_front();
+            match task {
+                Some(task) => task(),
+                None => break,
+            }
+        }
+    }
+
+    fn close(&self) {
+        *self.window.lock().unwrap_or_else(PoisonError::into_inner) = None;
+        let tasks = std::mem::take(&mut *self.lock_queue());
+        drop(tasks);
+    }
+
+    fn lock_queue(&self) -> std::sync::MutexGuard<'_, VecDeque<Task>> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Sends closures to run on the UI thread
+///
+/// Obtained from [`crate::Window::sender`]. Closures run in order inside
+/// [`crate::message_loop`]; a panic in one stops the loop and is resumed from there.
+///
+/// Closures cannot capture element wrappers directly; capture a [`crate::Handle`] and
+/// upgrade it inside the closure instead.
+#[derive(Clone)]
+pub struct UiSender {
+    shared: Arc<Shared>,
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<UiSender>();
+};
+
+thread_local! {
+    static SENDERS: RefCell<HashMap<u32, UiSender>> = RefCell::new(HashMap::new());
+}
+
+impl UiSender {
+    /// Get the sender for a window, creating it on first use
+    ///
+    /// # Safety
+    /// Must be called on the UI thread with a live window.
+    pub(crate) unsafe fn for_window(window: *mut sys::UIWindow) -> Self {
+        let id = (*window).e.id;
+        if let Some(sender) = SENDERS.with(|senders| senders.borrow().get(&id).cloned()) {
+            return sender;
+        }
+
+        let shared = Arc::new(Shared {
+            window: Mutex::new(Some(WindowPtr(window))),
+            queue: Mutex::new(VecDeque::new()),
+        });
+
+        let run = shared.clone();
+        registry::listen(
+            window.cast(),
+            message::user_message(RUN_QUEUED),
+            Box::new(move || run.run_queued()),
+        );
+        let close = shared.clone();
+        registry::listen(
+            window.cast(),
+            sys::UIMessage_UI_MSG_DESTROY,
+            Box::new(move || {
+                SENDERS.with(|senders| senders.borrow_mut().remove(&id));
+                close.close();
+            }),
+        );
+
+        let sender = Self { shared };
+        SENDERS.with(|senders| senders.borrow_mut().insert(id, sender.clone()));
+        sender
+    }
+
+    /// Queue `f` to run on the UI thread and wake the message loop
+    ///
+    /// # Errors
+    /// [`Error::Destroyed`] if the window has been closed; `f` is dropped unrun.
+    pub fn send(&self, f: impl FnOnce() + Send + 'static) -> Result<()> {
+        // Held while posting, so the window cannot be destroyed in between.
+        let window = self
+            .shared
+            .window
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let window = window.as_ref().ok_or(Error::Destroyed)?;
+        self.shared.lock_queue().push_back(Box::new(f));
+        unsafe {
+            sys::UIWindowPostMessage(
+                window.0,
+                message::user_message(RUN_QUEUED),
+                std::ptr::null_mut(),
+            )
+        };
+        Ok(())
+    }
+
+    /// Check whether the window still exists
+    pub fn is_alive(&self) -> bool {
+        self.shared
+            .window
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+}
@@ -0,0 +1,255 @@
+//! Golden-image tests: PNG files for [`Image`] and comparing captures against them.
+
+use crate::{Error, Image, Result};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Set to write captures over their golden images instead of comparing with them
+pub const UPDATE_SNAPSHOTS_VAR: &str = "LUIGI_UPDATE_SNAPSHOTS";
+
+impl Image {
+    /// Read a PNG file, giving every pixel an opaque alpha if the file has none
+    pub fn load_png(path: impl AsRef<Path>) -> Result<Image> {
+        let file = File::open(path).map_err(Error::Io)?;
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(decoding_error)?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(decoding_error)?;
+        let bytes = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgb => bytes
+                .chunks_exact(3)
+                .map(|p| argb(0xFF, p[0], p[1], p[2]))
+                .collect(),
+            png::ColorType::Rgba => bytes
+                .chunks_exact(4)
+                .map(|p| argb(p[3], p[0], p[1], p[2]))
+                .collect(),
+            png::ColorType::Grayscale => bytes.iter().map(|&v| argb(0xFF, v, v, v)).collect(),
+            png::ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .map(|p| argb(p[1], p[0], p[0], p[0]))
+                .collect(),
+            png::ColorType::Indexed => return Err(Error::InvalidImage),
+        };
+
+        Ok(Image::new(
+            info.width as usize,
+            info.height as usize,
+            pixels,
+        ))
+    }
+
+    /// Write the image as an RGB PNG file, dropping alpha
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        let file = File::create(path).map_err(Error::Io)?;
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(file),
+            self.width() as u32,
+            self.height() as u32,
+        );
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let bytes: Vec<u8> = self
+            .pixels()
+            .iter()
+            .flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8])
+            .collect();
+        let mut writer = encoder.write_header().map_err(encoding_error)?;
+        writer.write_image_data(&bytes).map_err(encoding_error)?;
+        writer.finish().map_err(encoding_error)
+    }
+}
+
+/// Compare `image` against the golden PNG at `path`
+///
+/// Pixels match when no colour channel differs by more than `tolerance`. On a
+/// mismatch, an image marking the differing pixels in red is written next to the
+/// golden one as `<name>.diff.png`, and [`Error::SnapshotMismatch`] is returned.
+///
+/// If the [`UPDATE_SNAPSHOTS_VAR`] environment variable is set, `image` is written to
+/// `path` instead, which is how golden images are created and updated.
+pub fn compare_snapshot(image: &Image, path: impl AsRef<Path>, tolerance: u8) -> Result<()> {
+    let update = std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some();
+    compare(image, path.as_ref(), tolerance, update)
+}
+
+/// [`compare_snapshot`] with the environment variable already read into `update`
+fn compare(image: &Image, path: &Path, tolerance: u8, update: bool) -> Result<()> {
+    if update {
+        return image.save_png(path);
+    }
+
+    let golden = Image::load_png(path)?;
+    let diff_path = diff_path(path);
+    match image.diff(&golden, tolerance) {
+        None => {
+            // Leave no diff from an earlier failing run behind.
+            let _ = std::fs::remove_file(&diff_path);
+            Ok(())
+        }
+        Some((differing, diff)) => {
+            diff.save_png(&diff_path)?;
+            Err(Error::SnapshotMismatch {
+                differing,
+                diff: diff_path,
+            })
+        }
+    }
+}
+
+/// Like [`compare_snapshot`], for tests
+///
+/// # Panics
+/// If the images differ or the golden image cannot be read, with a message saying
+/// where the diff was written and how to update the golden image.
+#[track_caller]
+pub fn assert_snapshot(image: &Image, path: impl AsRef<Path>, tolerance: u8) {
+    let path = path.as_ref();
+    match compare_snapshot(image, path, tolerance) {
+        Ok(()) => {}
+        Err(Error::SnapshotMismatch { differing, diff }) => panic!(
+            "{differing} pixels differ from {}, see {} (set {UPDATE_SNAPSHOTS_VAR}=1 to accept)",
+            path.display(),
+            diff.display(),
+        ),
+        Err(error) => panic!(
+            "cannot compare with {}: {error:?} (set {UPDATE_SNAPSHOTS_VAR}=1 to create it)",
+            path.display(),
+        ),
+    }
+}
+
+fn diff_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.diff.png"))
+}
+
+fn argb(a: u8, r: u8, g: u8, b: u8) -> u32 {
+    u32::from_be_bytes([a, r, g, b])
+}
+
+fn decoding_error(error: png::DecodingError) -> Error {
+    match error {
+        png::DecodingError::IoError(error) => Error::Io(error),
+        _ => Error::InvalidImage,
+    }
+}
+
+fn encoding_error(error: png::EncodingError) -> Error {
+    match error {
+        png::EncodingError::IoError(error) => Error::Io(error),
+        _ => Error::InvalidImage,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for one test, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("luigi-rs-{}-{name}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn gradient(width: usize, height: usize) -> Image {
+        let pixels = (0..width * height)
+            .map(|i| argb(0xFF, i as u8, (i * 7) as u8, (255 - i) as u8))
+            .collect();
+        Image::new(width, height, pixels)
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let dir = TempDir::new("round-trip");
+        let path = dir.0.join("image.png");
+        let image = gradient(5, 3);
+
+        image.save_png(&path).unwrap();
+        assert_eq!(Image::load_png(&path).unwrap(), image);
+    }
+
+    #[test]
+    fn alpha_is_dropped() {
+        let dir = TempDir::new("alpha");
+        let path = dir.0.join("image.png");
+
+        Image::new(1, 1, vec![0x00123456]).save_png(&path).unwrap();
+        assert_eq!(Image::load_png(&path).unwrap().pixels(), [0xFF123456]);
+    }
+
+    #[test]
+    fn missing_file() {
+        let dir = TempDir::new("missing");
+        let error = Image::load_png(dir.0.join("missing.png")).unwrap_err();
+        assert!(matches!(error, Error::Io(_)));
+    }
+
+    #[test]
+    fn invalid_file() {
+        let dir = TempDir::new("invalid");
+        let path = dir.0.join("image.png");
+        std::fs::write(&path, b"not a png").unwrap();
+        assert!(matches!(Image::load_png(&path), Err(Error::InvalidImage)));
+    }
+
+    #[test]
+    fn mismatch_writes_diff() {
+        let dir = TempDir::new("mismatch");
+        let golden = dir.0.join("window.png");
+        let image = gradient(4, 4);
+        image.save_png(&golden).unwrap();
+
+        let mut pixels = image.pixels().to_vec();
+        pixels[5] ^= 0x00FFFFFF;
+        let changed = Image::new(4, 4, pixels);
+
+        match compare(&changed, &golden, 0, false) {
+            Err(Error::SnapshotMismatch { differing, diff }) => {
+                assert_eq!(differing, 1);
+                assert_eq!(diff, dir.0.join("window.diff.png"));
+                let diff = Image::load_png(&diff).unwrap();
+                assert_eq!(diff.pixel(1, 1), Some(0xFFFF0000));
+            }
+            result => panic!("expected a mismatch, got {result:?}"),
+        }
+
+        // A passing comparison cleans up the diff of the failing one.
+        compare(&image, &golden, 0, false).unwrap();
+        assert!(!dir.0.join("window.diff.png").exists());
+    }
+
+    #[test]
+    fn update_overwrites_golden() {
+        let dir = TempDir::new("update");
+        let golden = dir.0.join("window.png");
+
+        // Creating a missing golden image
+        let image = gradient(4, 4);
+        compare(&image, &golden, 0, true).unwrap();
+        assert_eq!(Image::load_png(&golden).unwrap(), image);
+
+        // Replacing one that no longer matches
+        let changed = gradient(3, 2);
+        compare(&changed, &golden, 0, true).unwrap();
+        assert_eq!(Image::load_png(&golden).unwrap(), changed);
+        assert!(!dir.0.join("window.diff.png").exists());
+    }
+}
@@ -0,0 +1,213 @@
+//! Colors used by the built-in elements, mirroring `UITheme`.
+
+use crate::{sys, Color, Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+macro_rules! theme {
+    ($($(#[$meta:meta])* $field:ident: $raw:ident,)*) => {
+        /// Colors used by the built-in elements
+        ///
+        /// Themes can be written with [`fmt::Display`] and read back with [`str::parse`],
+        /// one `key = value` line per field:
+        ///
+        /// ```text
+        /// # Comments and blank lines are ignored
+        /// panel1 = #FF252B31
+        /// text = #FFFFFF
+        /// ```
+        ///
+        /// Colors are hex `AARRGGBB`, or `RRGGBB` for opaque colors. Fields that are not
+        /// listed keep their value from [`Theme::dark`].
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct Theme {
+            $($(#[$meta])* pub $field: Color,)*
+        }
+
+        impl Theme {
+            fn field_mut(&mut self, key: &str) -> Option<&mut Color> {
+                match key {
+                    $(stringify!($field) => Some(&mut self.$field),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl From<sys::UITheme> for Theme {
+            fn from(theme: sys::UITheme) -> Self {
+                Self {
+                    $($field: Color(theme.$raw),)*
+                }
+            }
+        }
+
+        impl From<Theme> for sys::UITheme {
+            fn from(theme: Theme) -> Self {
+                sys::UITheme {
+                    $($raw: theme.$field.0,)*
+                }
+            }
+        }
+
+        impl fmt::Display for Theme {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                $(writeln!(f, "{} = #{:08X}", stringify!($field), self.$field.0)?;)*
+                Ok(())
+            }
+        }
+    };
+}
+
+theme! {
+    /// Background of windows and panels
+    panel1: panel1,
+    /// Background of `UI_PANEL_WHITE` panels
+    panel2: panel2,
+    /// Highlight behind selected items
+    selected: selected,
+    border: border,
+    text: text,
+    text_disabled: textDisabled,
+    /// Text drawn over [`Theme::selected`]
+    text_selected: textSelected,
+    button_normal: buttonNormal,
+    button_hovered: buttonHovered,
+    button_pressed: buttonPressed,
+    button_disabled: buttonDisabled,
+    textbox_normal: textboxNormal,
+    textbox_focused: textboxFocused,
+    /// Background of the focused line in a code view
+    code_focused: codeFocused,
+    code_background: codeBackground,
+    code_default: codeDefault,
+    code_comment: codeComment,
+    code_string: codeString,
+    code_number: codeNumber,
+    code_operator: codeOperator,
+    code_preprocessor: codePreprocessor,
+}
+
+impl Theme {
+    /// The dark theme Luigi starts with
+    pub fn dark() -> Self {
+        unsafe { sys::LuigiRsThemeDark() }.into()
+    }
+
+    /// The light theme
+    pub fn classic() -> Self {
+        unsafe { sys::LuigiRsThemeClassic() }.into()
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl FromStr for Theme {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut theme = Theme::dark();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || Error::InvalidTheme { line: index + 1 };
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let field = theme.field_mut(key.trim()).ok_or_else(invalid)?;
+            *field = parse_color(value.trim()).ok_or_else(invalid)?;
+        }
+        Ok(theme)
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let digits = value
+        .strip_prefix('#')
+        .or_else(|| value.strip_prefix("0x"))
+        .unwrap_or(value);
+    // `from_str_radix` would also take a leading sign.
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let color = u32::from_str_radix(digits, 16).ok()?;
+    match digits.len() {
+        6 => Some(Color(color | 0xFF000000)),
+        8 => Some(Color(color)),
+        _ => None,
+    }
+}
+
+/// Get the theme currently used by the built-in elements
+pub fn theme() -> Theme {
+    unsafe { sys::LuigiRsGetTheme() }.into()
+}
+
+/// Change the theme and repaint every open window
+///
+/// [`crate::init`] resets the theme to [`Theme::dark`], so call this afterwards.
+pub fn set_theme(theme: &Theme) {
+    unsafe { sys::LuigiRsSetTheme((*theme).into()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for theme in [Theme::dark(), Theme::classic()] {
+            assert_eq!(theme.to_string().parse::<Theme>().unwrap(), theme);
+        }
+    }
+
+    #[test]
+    fn parse_defaults_and_comments() {
+        let theme: Theme = "# comment\n\n  text = #123456\npanel1=0x80ABCDEF\n"
+            .parse()
+            .unwrap();
+        assert_eq!(theme.text, Color(0xFF123456));
+        assert_eq!(theme.panel1, Color(0x80ABCDEF));
+        assert_eq!(theme.border, Theme::dark().border);
+    }
+
+    #[test]
+    fn parse_color_formats() {
+        assert_eq!(parse_color("#ffffff"), Some(Color(0xFFFFFFFF)));
+        assert_eq!(parse_color("00000000"), Some(Color(0)));
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("#1234567"), None);
+        assert_eq!(parse_color("#12345G"), None);
+        assert_eq!(parse_color("#+12345"), None);
+        assert_eq!(parse_color(""), None);
+    }
+
+    #[test]
+    fn malformed_color() {
+        let error = "text = #FFF\nborder = #000000"
+            .parse::<Theme>()
+            .unwrap_err();
+        assert!(matches!(error, Error::InvalidTheme { line: 1 }));
+        let error = "text = #FFFFFF\nborder = black"
+            .parse::<Theme>()
+            .unwrap_err();
+        assert!(matches!(error, Error::InvalidTheme { line: 2 }));
+    }
+
+    #[test]
+    fn unknown_key() {
+        let error = "\ntext = #FFFFFF\nbackground = #000000"
+            .parse::<Theme>()
+            .unwrap_err();
+        assert!(matches!(error, Error::InvalidTheme { line: 3 }));
+    }
+
+    #[test]
+    fn missing_separator() {
+        let error = "text #FFFFFF".parse::<Theme>().unwrap_err();
+        assert!(matches!(error, Error::InvalidTheme { line: 1 }));
+    }
+}
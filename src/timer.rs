@@ -0,0 +1,168 @@
+//! Timeouts and intervals run on the UI thread.
+//!
+//! Deadlines are kept in a heap on the UI thread. The message loops in `wrapper.c`
+//! fire the timers that are due before waiting for events, and wait no longer than
+//! until the next deadline.
+
+use crate::{registry, sys, unwind};
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::os::raw::c_int;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+struct Entry {
+    window: u32,
+    interval: Option<Duration>,
+    callback: Rc<RefCell<Box<dyn FnMut()>>>,
+}
+
+thread_local! {
+    static TIMERS: RefCell<HashMap<u64, Entry>> = RefCell::new(HashMap::new());
+    // Earliest first, ids break ties so timers due together fire in the order they were set.
+    static DEADLINES: RefCell<BinaryHeap<Reverse<(Instant, u64)>>> =
+        const { RefCell::new(BinaryHeap::new()) };
+    static WATCHED: RefCell<HashSet<u32>> = RefCell::new(HashSet::new());
+    static NEXT_TIMER: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A pending timeout or interval, returned by [`crate::Window::set_timeout`] and
+/// [`crate::Window::set_interval`]
+///
+/// Dropping the handle does not cancel the timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timer {
+    id: u64,
+}
+
+impl Timer {
+    /// Stop the timer; the callback is dropped and will not run again
+    pub fn cancel(self) {
+        let entry = TIMERS.with(|timers| timers.borrow_mut().remove(&self.id));
+        if entry.is_some() {
+            unschedule(|id| id == self.id);
+        }
+        drop(entry);
+    }
+
+    /// Check whether the timer will still run
+    pub fn is_active(&self) -> bool {
+        TIMERS.with(|timers| timers.borrow().contains_key(&self.id))
+    }
+}
+
+/// # Safety
+/// Must be called on the UI thread with a live window.
+pub(crate) unsafe fn start(
+    window: *mut sys::UIWindow,
+    delay: Duration,
+    interval: Option<Duration>,
+    callback: Box<dyn FnMut()>,
+) -> Timer {
+    let window_id = (*window).e.id;
+    if WATCHED.with(|watched| watched.borrow_mut().insert(window_id)) {
+        let forget = move || forget_window(window_id);
+        registry::listen(
+            window.cast(),
+            sys::UIMessage_UI_MSG_DESTROY,
+            Box::new(forget),
+        );
+    }
+    sys::LuigiRsSetTimers(Some(fire_due));
+
+    let id = NEXT_TIMER.with(|next| next.replace(next.get() + 1));
+    let entry = Entry {
+        window: window_id,
+        interval,
+        callback: Rc::new(RefCell::new(callback)),
+    };
+    TIMERS.with(|timers| timers.borrow_mut().insert(id, entry));
+    schedule(Instant::now() + delay, id);
+    Timer { id }
+}
+
+/// Time left until the next timer is due, if any is pending
+pub(crate) fn next_due() -> Option<Duration> {
+    let next = DEADLINES.with(|deadlines| deadlines.borrow().peek().map(|next| next.0 .0))?;
+    Some(next.saturating_duration_since(Instant::now()))
+}
+
+fn schedule(at: Instant, id: u64) {
+    DEADLINES.with(|deadlines| deadlines.borrow_mut().push(Reverse((at, id))));
+}
+
+fn unschedule(mut cancelled: impl FnMut(u64) -> bool) {
+    DEADLINES.with(|deadlines| {
+        deadlines
+            .borrow_mut()
+            .retain(|Reverse((_, id))| !cancelled(*id))
+    });
+}
+
+/// Fire the timers that are due, returning the milliseconds until the next one or -1
+unsafe extern "C" fn fire_due(fired: *mut bool) -> c_int {
+    unwind::catch(-1, || {
+        let now = Instant::now();
+        while let Some((at, id)) = pop_due(now) {
+            *fired = true;
+            fire(at, id);
+        }
+        // Rounded up, so the loop does not wake just before the deadline.
+        next_due().map_or(-1, |left| {
+            left.as_micros().div_ceil(1000).min(c_int::MAX as u128) as c_int
+        })
+    })
+}
+
+fn pop_due(now: Instant) -> Option<(Instant, u64)> {
+    DEADLINES.with(|deadlines| {
+        let mut deadlines = deadlines.borrow_mut();
+        let Reverse((at, id)) = *deadlines.peek()?;
+        if at > now {
+            return None;
+        }
+        deadlines.pop();
+        Some((at, id))
+    })
+}
+
+fn fire(at: Instant, id: u64) {
+    let callback = TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        let entry = timers.get_mut(&id)?;
+        let callback = entry.callback.clone();
+        match entry.interval {
+            // Keep to the original schedule unless the loop fell behind.
+            Some(interval) => schedule((at + interval).max(Instant::now()), id),
+            None => {
+                timers.remove(&id);
+            }
+        }
+        Some(callback)
+    });
+
+    if let Some(callback) = callback {
+        if let Ok(mut callback) = callback.try_borrow_mut() {
+            callback();
+        }
+    }
+}
+
+fn forget_window(window: u32) {
+    WATCHED.with(|watched| watched.borrow_mut().remove(&window));
+    let removed = TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        let ids = timers
+            .iter()
+            .filter(|(_, entry)| entry.window == window)
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+        ids.into_iter()
+            .filter_map(|id| timers.remove(&id).map(|entry| (id, entry)))
+            .collect::<Vec<_>>()
+    });
+    unschedule(|id| removed.iter().any(|(removed, _)| *removed == id));
+    // Dropped outside the borrow, since callbacks may own elements.
+    drop(removed);
+}
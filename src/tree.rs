@@ -0,0 +1,156 @@
+//! Walking the element tree.
+
+use crate::{registry, sys, Element, Widget};
+use std::ffi::CStr;
+use std::fmt::Write;
+
+/// An element of any type, as found by walking the tree
+///
+/// Use [`Element::class_name`] to tell what it is.
+#[derive(Debug)]
+pub struct AnyElement {
+    raw: *mut sys::UIElement,
+    id: u32,
+}
+
+impl AnyElement {
+    /// Wrap an element found through a pointer of another element, if it is already
+    /// known to the registry
+    ///
+    /// Walking the tree does not register elements, since that would hook Luigi's own
+    /// elements just by looking at them.
+    ///
+    /// # Safety
+    /// `raw` must point to an element that has not been freed.
+    pub(crate) unsafe fn wrap(raw: *mut sys::UIElement) -> Option<Self> {
+        let id = (*raw).id;
+        registry::is_alive(raw, id).then_some(Self { raw, id })
+    }
+}
+
+impl Element for AnyElement {
+    fn raw_element(&self) -> *mut sys::UIElement {
+        self.raw
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Widget for AnyElement {
+    unsafe fn from_raw(raw: *mut sys::UIElement, id: u32) -> Self {
+        Self { raw, id }
+    }
+}
+
+/// Iterator over the children of an element, from [`Element::children`]
+///
+/// The children are collected when the iterator is created, so elements can be
+/// created or destroyed while iterating. Elements that Luigi creates itself, such as
+/// the scroll bar of a table, are skipped unless they have been wrapped before.
+pub struct Children {
+    inner: std::vec::IntoIter<AnyElement>,
+}
+
+impl Children {
+    /// # Safety
+    /// `parent` must point to a live element.
+    pub(crate) unsafe fn new(parent: *mut sys::UIElement) -> Self {
+        let children = raw_children(parent)
+            .into_iter()
+            .filter_map(|child| AnyElement::wrap(child))
+            .collect::<Vec<_>>();
+        Self {
+            inner: children.into_iter(),
+        }
+    }
+}
+
+impl Iterator for Children {
+    type Item = AnyElement;
+
+    fn next(&mut self) -> Option<AnyElement> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Get the children that are not being destroyed, in layout order
+///
+/// # Safety
+/// `parent` must point to a live element.
+unsafe fn raw_children(parent: *mut sys::UIElement) -> Vec<*mut sys::UIElement> {
+    let mut children = Vec::new();
+    let mut child = (*parent).children;
+    while !child.is_null() {
+        // Children marked for destruction are only unlinked on the next update.
+        if (*child).flags & sys::UI_ELEMENT_DESTROY == 0 {
+            children.push(child);
+        }
+        child = (*child).next;
+    }
+    children
+}
+
+/// Get the nearest ancestor that is known to the registry
+///
+/// # Safety
+/// `element` must point to a live element.
+pub(crate) unsafe fn parent(element: *mut sys::UIElement) -> Option<AnyElement> {
+    let mut parent = (*element).parent;
+    while !parent.is_null() {
+        if let Some(parent) = AnyElement::wrap(parent) {
+            return Some(parent);
+        }
+        parent = (*parent).parent;
+    }
+    None
+}
+
+/// # Safety
+/// `element` must point to a live element.
+pub(crate) unsafe fn class_name(element: *mut sys::UIElement) -> String {
+    let name = (*element).cClassName;
+    if name.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(name).to_string_lossy().into_owned()
+    }
+}
+
+/// Format `element` and its descendants, one element per line
+///
+/// Elements Luigi created itself are included, since nothing is wrapped.
+///
+/// # Safety
+/// `element` must point to a live element.
+pub(crate) unsafe fn dump(element: *mut sys::UIElement) -> String {
+    let mut out = String::new();
+    dump_into(&mut out, element, 0);
+    out
+}
+
+unsafe fn dump_into(out: &mut String, element: *mut sys::UIElement, depth: usize) {
+    let bounds = (*element).bounds;
+    let hidden = (*element).flags & sys::UI_ELEMENT_HIDE != 0;
+    let _ = writeln!(
+        out,
+        "{:indent$}{} #{} ({}, {}) {}x{}{}",
+        "",
+        class_name(element),
+        (*element).id,
+        bounds.l,
+        bounds.t,
+        bounds.r - bounds.l,
+        bounds.b - bounds.t,
+        if hidden { " hidden" } else { "" },
+        indent = depth * 2,
+    );
+    for child in raw_children(element) {
+        dump_into(out, child, depth + 1);
+    }
+}
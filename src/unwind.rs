@@ -0,0 +1,37 @@
+//! Keeps panics in Rust callbacks from unwinding through Luigi's C frames.
+//!
+//! A panicking callback stops the message loop, and the panic is resumed once
+//! [`crate::message_loop`] is back in Rust.
+
+use crate::sys;
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+
+thread_local! {
+    static PAYLOAD: RefCell<Option<Box<dyn Any + Send>>> = const { RefCell::new(None) };
+}
+
+/// Run a callback invoked from C, returning `default` if it panics
+///
+/// Once a callback has panicked, later callbacks are skipped until the panic is resumed.
+pub(crate) fn catch<R>(default: R, f: impl FnOnce() -> R) -> R {
+    if PAYLOAD.with(|payload| payload.borrow().is_some()) {
+        return default;
+    }
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            PAYLOAD.with(|slot| *slot.borrow_mut() = Some(payload));
+            unsafe { sys::LuigiRsQuit() };
+            default
+        }
+    }
+}
+
+/// Re-raise a panic caught in a callback, if there was one
+pub(crate) fn resume() {
+    if let Some(payload) = PAYLOAD.with(|payload| payload.borrow_mut().take()) {
+        panic::resume_unwind(payload);
+    }
+}
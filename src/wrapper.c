@@ -1,4 +1,6 @@
-#ifdef _WIN32
+#if defined(UI_HEADLESS)
+    #include "headless.h"
+#elif defined(_WIN32)
     #define UI_WINDOWS
 #elif __linux__
     #define UI_LINUX
@@ -8,3 +10,378 @@
 
 #define UI_IMPLEMENTATION
 #include "../luigi.h"
+
+#if defined(UI_HEADLESS)
+#include "headless.c"
+#elif defined(UI_LINUX)
+#include <poll.h>
+#endif
+
+// Access to library internals that luigi.h does not expose
+
+void LuigiRsQuit() {
+    ui.quit = true;
+}
+
+UITheme LuigiRsGetTheme() {
+    return ui.theme;
+}
+
+void LuigiRsSetTheme(UITheme theme) {
+    ui.theme = theme;
+
+    for (UIWindow *window = ui.windows; window; window = window->next) {
+        UIElementRepaint(&window->e, NULL);
+    }
+}
+
+UITheme LuigiRsThemeClassic() {
+    return _uiThemeClassic;
+}
+
+UITheme LuigiRsThemeDark() {
+    return _uiThemeDark;
+}
+
+void LuigiRsFontFree(UIFont *font) {
+    // Only for fonts that FreeType failed to open, which own nothing else.
+    UI_FREE(font);
+}
+
+static bool luigiRsLoopStarted;
+static int luigiRsLoopResult;
+static int (*luigiRsTimers)(bool *fired);
+
+void LuigiRsSetTimers(int (*timers)(bool *fired)) {
+    luigiRsTimers = timers;
+}
+
+static bool LuigiRsEventsPending() {
+#if defined(UI_HEADLESS)
+    // Resized windows are laid out here, as they would be by a ConfigureNotify.
+    LuigiRsHeadlessConfigure();
+    return LuigiRsHeadlessPending();
+#elif defined(UI_LINUX)
+    return XPending(ui.display) > 0;
+#else
+    MSG message;
+    return PeekMessage(&message, NULL, 0, 0, PM_NOREMOVE);
+#endif
+}
+
+// Waits at most timeout milliseconds for an event, returning whether one arrived.
+static bool LuigiRsWaitForEvents(int timeout) {
+    if (LuigiRsEventsPending()) return true;
+#if defined(UI_HEADLESS)
+    return LuigiRsHeadlessWait(timeout);
+#elif defined(UI_LINUX)
+    struct pollfd connection = { .fd = ConnectionNumber(ui.display), .events = POLLIN };
+    return poll(&connection, 1, timeout) > 0;
+#else
+    return MsgWaitForMultipleObjects(0, NULL, FALSE, timeout, QS_ALLINPUT) == WAIT_OBJECT_0;
+#endif
+}
+
+// Fires the timers that are due, returning the milliseconds until the next one or -1.
+static int LuigiRsFireTimers(bool *fired) {
+    *fired = false;
+    int timeout = luigiRsTimers ? luigiRsTimers(fired) : -1;
+    // Paint whatever the callbacks, or code outside the loop, changed.
+    _UIUpdate();
+    return timeout;
+}
+
+// _UIMessageLoopSingle, but timers count as events and it blocks no longer than
+// until the next one is due.
+static bool LuigiRsLoopSingle(int *result) {
+    bool fired;
+    int timeout = LuigiRsFireTimers(&fired);
+    if (fired || ui.quit || ui.dialogResult) return true;
+
+    if (timeout >= 0 && !ui.animating && !LuigiRsWaitForEvents(timeout)) {
+        LuigiRsFireTimers(&fired);
+        return true;
+    }
+
+    return _UIMessageLoopSingle(result);
+}
+
+static void LuigiRsMessageLoopStep() {
+    // Mirrors the body of UIMessageLoop, which stops for good once this fails.
+    if (LuigiRsLoopSingle(&luigiRsLoopResult)) {
+        ui.dialogResult = NULL;
+    } else {
+        ui.quit = true;
+    }
+}
+
+static void LuigiRsMessageLoopStart() {
+    if (!luigiRsLoopStarted) {
+        luigiRsLoopStarted = true;
+        _UIInspectorCreate();
+        _UIUpdate();
+    }
+}
+
+int LuigiRsMessageLoop() {
+    // UIMessageLoop, with the loop body above.
+    LuigiRsMessageLoopStart();
+#ifdef UI_AUTOMATION_TESTS
+    return UIAutomationRunTests();
+#else
+    while (!ui.quit) LuigiRsMessageLoopStep();
+    return luigiRsLoopResult;
+#endif
+}
+
+bool LuigiRsMessageLoopSingle(bool onlyPending, int *result) {
+    LuigiRsMessageLoopStart();
+
+    if (!onlyPending) {
+        if (!ui.quit) LuigiRsMessageLoopStep();
+    } else {
+        bool fired;
+        LuigiRsFireTimers(&fired);
+        while (!ui.quit && LuigiRsEventsPending()) LuigiRsMessageLoopStep();
+        if (!ui.quit && ui.animating) _UIProcessAnimations();
+    }
+
+    *result = luigiRsLoopResult;
+    return !ui.quit;
+}
+
+bool LuigiRsIsAnimating() {
+    return ui.animating != NULL;
+}
+
+int LuigiRsConnectionNumber() {
+#ifdef UI_LINUX
+    return ui.display ? ConnectionNumber(ui.display) : -1;
+#else
+    return -1;
+#endif
+}
+
+// Dialogs built from Rust, split into the steps of UIDialogShow
+
+UIElement *LuigiRsDialogBegin(UIWindow *window) {
+    if (window->dialog) return NULL;
+    window->dialog = UIElementCreate(sizeof(UIElement), &window->e, 0, _UIDialogWrapperMessage, "DialogWrapper");
+    UIPanel *panel = UIPanelCreate(window->dialog, UI_PANEL_MEDIUM_SPACING | UI_PANEL_GRAY | UI_PANEL_EXPAND);
+    panel->border = UI_RECT_1(UI_SIZE_PANE_MEDIUM_BORDER * 2);
+    if (window->e.children != window->dialog) window->e.children->flags |= UI_ELEMENT_DISABLED;
+    return window->dialog;
+}
+
+void LuigiRsDialogOpen(UIWindow *window, UIElement *focus) {
+    window->dialogOldFocus = window->focused;
+    UIElementFocus(focus ? focus : window->dialog);
+    for (int i = 1; i <= 3; i++) _UIWindowSetPressed(window, NULL, i);
+    UIElementRefresh(&window->e);
+}
+
+bool LuigiRsDialogRun(UIWindow *window, UIElement *focus) {
+    LuigiRsDialogOpen(window, focus);
+
+    int result;
+    ui.dialogResult = NULL;
+    _UIUpdate();
+    while (!ui.dialogResult && !ui.quit && LuigiRsLoopSingle(&result));
+    ui.quit = !ui.dialogResult;
+    return ui.dialogResult != NULL;
+}
+
+void LuigiRsDialogClose() {
+    ui.dialogResult = "";
+}
+
+void LuigiRsDialogEnd(UIWindow *window) {
+    if (window->e.children != window->dialog) window->e.children->flags &= ~UI_ELEMENT_DISABLED;
+    UIElementDestroy(window->dialog);
+    window->dialog = NULL;
+    UIElementRefresh(&window->e);
+    if (window->dialogOldFocus) UIElementFocus(window->dialogOldFocus);
+}
+
+void LuigiRsClipboardWriteText(UIWindow *window, const char *text) {
+    // Luigi takes ownership of the copy on X11 and headless, and only reads it on Windows.
+    ptrdiff_t bytes = _UIStringLength(text);
+    char *copy = (char *) UI_CALLOC(bytes + 1);
+    for (ptrdiff_t i = 0; i < bytes; i++) copy[i] = text[i];
+    _UIClipboardWriteText(window, copy);
+#ifdef UI_WINDOWS
+    UI_FREE(copy);
+#endif
+}
+
+char *LuigiRsClipboardReadTextStart(UIWindow *window, size_t *bytes) {
+    return _UIClipboardReadTextStart(window, bytes);
+}
+
+void LuigiRsClipboardReadTextEnd(UIWindow *window, char *text) {
+    _UIClipboardReadTextEnd(window, text);
+}
+
+UIWindow *LuigiRsWindows() {
+    return ui.windows;
+}
+
+void LuigiRsWindowCapture(UIWindow *window) {
+#ifdef UI_HEADLESS
+    LuigiRsHeadlessConfigure();
+#endif
+    UIElementRepaint(&window->e, NULL);
+    _UIUpdate();
+}
+
+#ifdef UI_DEBUG
+// The inspector and paint statistics
+
+bool LuigiRsInspectorLog(const char *text) {
+    // The inspector is only created once the message loop starts.
+    if (!ui.inspectorLog) return false;
+    UIInspectorLog("%s", text);
+    return true;
+}
+
+// Luigi computes lastFullFillCount after clearing the update region it divides by, so
+// windows count their fills themselves. While a window paints, a counter element is
+// linked after its last child, where the painter holds the total of the pass.
+
+typedef struct LuigiRsFillCounter {
+    UIElement e;
+    UIWindow *window;
+    int (*windowMessage)(UIElement *element, UIMessage message, int di, void *dp);
+    int start, fillCount;
+    UIRectangle pass, region;
+    struct LuigiRsFillCounter *next;
+} LuigiRsFillCounter;
+
+static LuigiRsFillCounter *luigiRsFillCounters;
+
+static LuigiRsFillCounter *LuigiRsFindFillCounter(UIWindow *window) {
+    for (LuigiRsFillCounter *counter = luigiRsFillCounters; counter; counter = counter->next) {
+        if (counter->window == window) return counter;
+    }
+
+    return NULL;
+}
+
+static void LuigiRsUnlinkFillCounter(LuigiRsFillCounter *counter) {
+    for (UIElement **link = &counter->window->e.children; *link; link = &(*link)->next) {
+        if (*link == &counter->e) {
+            *link = counter->e.next;
+            counter->e.next = NULL;
+            return;
+        }
+    }
+}
+
+static int LuigiRsFillCounterMessage(UIElement *element, UIMessage message, int di, void *dp) {
+    (void) di;
+    LuigiRsFillCounter *counter = (LuigiRsFillCounter *) element;
+
+    if (message == UI_MSG_PAINT) {
+        counter->fillCount = ((UIPainter *) dp)->fillCount - counter->start;
+        counter->region = counter->pass;
+        // The painting loop has already read the child before this one.
+        LuigiRsUnlinkFillCounter(counter);
+    }
+
+    return 0;
+}
+
+static int LuigiRsCountedWindowMessage(UIElement *element, UIMessage message, int di, void *dp) {
+    LuigiRsFillCounter *counter = LuigiRsFindFillCounter((UIWindow *) element);
+    int (*windowMessage)(UIElement *, UIMessage, int, void *) = counter->windowMessage;
+
+    if (message == UI_MSG_PAINT) {
+        UIPainter *painter = (UIPainter *) dp;
+        counter->start = painter->fillCount;
+        counter->pass = painter->clip;
+        counter->e.clip = element->clip;
+        LuigiRsUnlinkFillCounter(counter);
+        UIElement **link = &element->children;
+        while (*link) link = &(*link)->next;
+        *link = &counter->e;
+    } else if (message == UI_MSG_DESTROY) {
+        LuigiRsUnlinkFillCounter(counter);
+        LuigiRsFillCounter **link = &luigiRsFillCounters;
+        while (*link != counter) link = &(*link)->next;
+        *link = counter->next;
+        element->messageClass = windowMessage;
+        UI_FREE(counter);
+    }
+
+    return windowMessage(element, message, di, dp);
+}
+
+void LuigiRsWindowCountFills(UIWindow *window) {
+    if (LuigiRsFindFillCounter(window)) return;
+    LuigiRsFillCounter *counter = (LuigiRsFillCounter *) UI_CALLOC(sizeof(LuigiRsFillCounter));
+    counter->e.messageClass = LuigiRsFillCounterMessage;
+    counter->e.cClassName = "FillCounter";
+    counter->window = window;
+    counter->windowMessage = window->e.messageClass;
+    counter->fillCount = -1;
+    counter->next = luigiRsFillCounters;
+    luigiRsFillCounters = counter;
+    window->e.messageClass = LuigiRsCountedWindowMessage;
+}
+
+bool LuigiRsWindowLastFills(UIWindow *window, int *fillCount, int *area) {
+    LuigiRsFillCounter *counter = LuigiRsFindFillCounter(window);
+    if (!counter || counter->fillCount < 0) return false;
+    *fillCount = counter->fillCount;
+    *area = UI_RECT_WIDTH(counter->region) * UI_RECT_HEIGHT(counter->region);
+    return true;
+}
+#endif
+
+#ifdef UI_AUTOMATION_TESTS
+// Luigi's automation harness, driven from Rust
+
+static int (*luigiRsAutomationTests)(void);
+
+void LuigiRsAutomationSetTests(int (*tests)(void)) {
+    luigiRsAutomationTests = tests;
+}
+
+static bool LuigiRsAutomationAwaitingLayout() {
+    for (UIWindow *window = ui.windows; window; window = window->next) {
+        if ((~window->e.flags & UI_WINDOW_MENU) && !UI_RECT_VALID(window->e.bounds)) return true;
+    }
+
+    return false;
+}
+
+int UIAutomationRunTests() {
+    // Called by LuigiRsMessageLoop in place of the event loop.
+    int result = 0;
+
+    if (luigiRsAutomationTests) {
+        // Windows get their first size from the platform, and tests need it to click.
+#ifdef UI_HEADLESS
+        LuigiRsHeadlessConfigure();
+#else
+        while (!ui.quit && LuigiRsAutomationAwaitingLayout() && LuigiRsLoopSingle(&result));
+#endif
+        if (ui.quit) return result;
+        return luigiRsAutomationTests();
+    }
+
+    while (!ui.quit && LuigiRsLoopSingle(&result)) ui.dialogResult = NULL;
+    return result;
+}
+
+bool LuigiRsAutomationClick(UIElement *element) {
+    if (!UI_RECT_VALID(element->clip)) return false;
+    UIWindow *window = element->window;
+    window->cursorX = (element->clip.l + element->clip.r) / 2;
+    window->cursorY = (element->clip.t + element->clip.b) / 2;
+    _UIWindowInputEvent(window, UI_MSG_MOUSE_MOVE, 0, 0);
+    _UIWindowInputEvent(window, UI_MSG_LEFT_DOWN, 0, 0);
+    _UIWindowInputEvent(window, UI_MSG_LEFT_UP, 0, 0);
+    return true;
+}
+#endif
@@ -0,0 +1,51 @@
+//! Tweens on the headless backend, run with `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{self as ui, Easing, Gauge, Window};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn tween_takes_its_duration() {
+    ui::init();
+
+    let window = Window::new("Animation", 200, 100, 0).expect("Failed to create window");
+    let gauge = Gauge::new(&window, 0).expect("Failed to create gauge");
+
+    let progress = Rc::new(Cell::new(0.0));
+    let last = progress.clone();
+    let start = Instant::now();
+    let animation = ui::tween(
+        &gauge,
+        Duration::from_millis(200),
+        Easing::Linear,
+        move |t| last.set(t),
+    )
+    .expect("Failed to start tween");
+
+    // Paced like an external loop, so the process barely uses CPU time meanwhile
+    while animation.is_running() {
+        assert!(
+            start.elapsed() < Duration::from_secs(2),
+            "tween did not end"
+        );
+        thread::sleep(Duration::from_millis(10));
+        ui::pump_pending();
+    }
+
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed >= Duration::from_millis(200),
+        "ended after {elapsed:?}"
+    );
+    assert!(
+        elapsed < Duration::from_millis(400),
+        "ended after {elapsed:?}"
+    );
+    assert_eq!(progress.get(), 1.0);
+}
@@ -0,0 +1,78 @@
+//! Callback lifetimes on the headless backend, run with `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{self as ui, Button, Element, Key, Label, Panel, Response, Shortcut, Window};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Counts how many of the closures capturing it have been dropped
+struct Guard(Rc<Cell<u32>>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+impl Guard {
+    /// Use the guard, so closures capture it
+    fn touch(&self) {}
+}
+
+#[test]
+fn dropped_on_destroy() {
+    ui::init();
+
+    let dropped = Rc::new(Cell::new(0));
+    let guard = || Guard(dropped.clone());
+
+    let mut window = Window::new("Callbacks", 200, 100, 0).expect("Failed to create window");
+    let mut panel = Panel::new(&window, 0).expect("Failed to create panel");
+    let button = Button::new(&panel, 0, "Button").expect("Failed to create button");
+    let label = Label::new(&panel, 0, "Label").expect("Failed to create label");
+
+    let first = guard();
+    button
+        .invoke(Box::new(move || first.touch()))
+        .expect("Failed to set callback");
+    // Replacing a callback drops the previous one straight away
+    let second = guard();
+    button
+        .invoke(Box::new(move || second.touch()))
+        .expect("Failed to set callback");
+    assert_eq!(dropped.get(), 1);
+
+    let handler = guard();
+    label
+        .on_message(move |_, _| {
+            handler.touch();
+            Response::Unhandled
+        })
+        .expect("Failed to set handler");
+    let shortcut = guard();
+    window
+        .register_shortcut(Shortcut::new(
+            Key::Letter('S').code(),
+            true,
+            false,
+            false,
+            move || shortcut.touch(),
+        ))
+        .expect("Failed to register shortcut");
+
+    ui::pump_pending();
+    assert_eq!(dropped.get(), 1);
+
+    // Element callbacks go with their element
+    panel.destroy().expect("Failed to destroy panel");
+    ui::pump_pending();
+    assert_eq!(dropped.get(), 3);
+
+    // Shortcuts go with their window
+    window.destroy().expect("Failed to destroy window");
+    ui::pump_pending();
+    assert_eq!(dropped.get(), 4);
+}
@@ -0,0 +1,76 @@
+//! Dialog keyboard handling on the headless backend, run with `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{self as ui, Dialog, Key, Widget, Window};
+
+/// Queue keys for the window to receive once the dialog's loop is running
+fn queue(window: &Window, text: &'static str, key: Key) {
+    let handle = window.handle();
+    window
+        .sender()
+        .expect("Failed to get sender")
+        .send(move || {
+            let window = handle.upgrade().expect("Window destroyed");
+            window.type_text(text).expect("Failed to type");
+            window.press_key(key).expect("Failed to press key");
+        })
+        .expect("Failed to send");
+}
+
+#[test]
+fn enter_and_escape() {
+    ui::init();
+
+    let window = Window::new("Dialog", 300, 200, 0).expect("Failed to create window");
+    let mut name = String::from("Luigi");
+
+    // Enter in the textbox presses the default button and keeps the edit
+    queue(&window, " Mario", Key::Enter);
+    let result = Dialog::new()
+        .label("Name:")
+        .textbox(&mut name)
+        .row()
+        .button("Apply")
+        .default_button("OK")
+        .cancel_button("Cancel")
+        .show(&window)
+        .expect("Failed to show dialog");
+    assert!(result.pressed("OK"));
+    assert_eq!(result.texts, ["Luigi Mario"]);
+    assert_eq!(name, "Luigi Mario");
+
+    // Escape presses the cancel button and discards the edit
+    queue(&window, "!", Key::Escape);
+    let result = Dialog::new()
+        .textbox(&mut name)
+        .row()
+        .button("OK")
+        .cancel_button("Cancel")
+        .show(&window)
+        .expect("Failed to show dialog");
+    assert!(result.pressed("Cancel"));
+    assert_eq!(result.texts, ["Luigi Mario!"]);
+    assert_eq!(name, "Luigi Mario");
+
+    // Without a cancel button, Escape closes the dialog without one
+    queue(&window, "", Key::Escape);
+    let result = Dialog::new()
+        .label("Done")
+        .button("OK")
+        .show(&window)
+        .expect("Failed to show dialog");
+    assert_eq!(result.button, None);
+
+    // Without a default button, Enter presses the first one
+    queue(&window, "", Key::Enter);
+    let result = Dialog::new()
+        .label("Continue?")
+        .button("Yes")
+        .button("No")
+        .show(&window)
+        .expect("Failed to show dialog");
+    assert!(result.pressed("Yes"));
+}
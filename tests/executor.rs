@@ -0,0 +1,82 @@
+//! Futures on the UI thread on the headless backend, run with `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{self as ui, Button, Dialog, Element, Error, Key, MouseButton, Widget, Window};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::thread;
+
+#[test]
+fn spawn_local_tasks() {
+    ui::init();
+
+    let window = Window::new("Executor", 200, 100, 0).expect("Failed to create window");
+    let button = Button::new(&window, 0, "Button").expect("Failed to create button");
+    window.resize(200, 100).expect("Failed to lay out window");
+    let ui_thread = thread::current().id();
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let mut clicks = button.clicks().expect("Failed to listen for clicks");
+    let (task_log, handle) = (log.clone(), window.handle());
+    window
+        .spawn_local(async move {
+            for _ in 0..2 {
+                clicks.next().await.expect("Button destroyed");
+                task_log.borrow_mut().push("click");
+            }
+            let worker = ui::spawn_blocking(move || thread::current().id()).await;
+            assert_ne!(worker, ui_thread);
+            assert_eq!(thread::current().id(), ui_thread);
+            task_log.borrow_mut().push("background");
+
+            // Dropping a dialog's future closes it
+            let mut window = handle.upgrade().expect("Window destroyed");
+            let pending = Dialog::new().button("OK").show_async(&window);
+            let pending = pending.expect("Failed to show dialog");
+            let second = Dialog::new().button("OK").show(&window);
+            assert!(matches!(second, Err(Error::DialogOpen)));
+            drop(pending);
+
+            // Keys typed while the task waits go to the dialog
+            let mut name = String::from("Luigi");
+            let pending = Dialog::new()
+                .textbox(&mut name)
+                .row()
+                .default_button("OK")
+                .cancel_button("Cancel")
+                .show_async(&window)
+                .expect("Failed to show dialog");
+            window
+                .sender()
+                .expect("Failed to get sender")
+                .send(move || {
+                    let window = handle.upgrade().expect("Window destroyed");
+                    window.type_text(" Mario").expect("Failed to type");
+                    window.press_key(Key::Enter).expect("Failed to press key");
+                })
+                .expect("Failed to send");
+            let result = pending.await;
+            assert!(result.pressed("OK"));
+            assert_eq!(name, "Luigi Mario");
+            task_log.borrow_mut().push("dialog");
+
+            // Destroying the last window ends the message loop
+            window.destroy().expect("Failed to destroy window");
+        })
+        .expect("Failed to spawn task");
+
+    // Both clicks arrive before the task gets to wait for the second one
+    let bounds = button.bounds().expect("Failed to get bounds");
+    let (x, y) = ((bounds.l + bounds.r) / 2, (bounds.t + bounds.b) / 2);
+    for _ in 0..2 {
+        window
+            .click(x, y, MouseButton::Left)
+            .expect("Failed to click");
+    }
+
+    ui::message_loop();
+    assert_eq!(*log.borrow(), ["click", "click", "background", "dialog"]);
+}
@@ -0,0 +1,44 @@
+//! Stale element handles on the headless backend, run with `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{self as ui, Button, Element, Error, Label, Panel, Widget, Window};
+
+#[test]
+fn destroyed_elements() {
+    ui::init();
+
+    let window = Window::new("Handles", 200, 100, 0).expect("Failed to create window");
+    let mut panel = Panel::new(&window, 0).expect("Failed to create panel");
+    let label = Label::new(&panel, 0, "Label").expect("Failed to create label");
+    let mut button = Button::new(&panel, 0, "Button").expect("Failed to create button");
+    let handle = label.handle();
+
+    let upgraded = handle.upgrade().expect("Label destroyed too early");
+    upgraded
+        .set_content("Upgraded")
+        .expect("Failed to set content");
+
+    // Luigi frees destroyed elements in the next update
+    panel.destroy().expect("Failed to destroy panel");
+    ui::pump_pending();
+
+    assert!(!handle.is_alive());
+    assert!(!label.is_alive());
+    assert!(matches!(handle.upgrade(), Err(Error::Destroyed)));
+    assert!(matches!(label.set_content("Stale"), Err(Error::Destroyed)));
+    assert!(matches!(upgraded.bounds(), Err(Error::Destroyed)));
+    assert!(matches!(button.refresh(), Err(Error::Destroyed)));
+    assert!(matches!(button.destroy(), Err(Error::Destroyed)));
+    assert!(matches!(panel.destroy(), Err(Error::Destroyed)));
+
+    // The window itself is untouched
+    assert!(window.is_alive());
+    assert!(window
+        .children()
+        .expect("Window destroyed")
+        .next()
+        .is_none());
+}
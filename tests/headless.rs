@@ -0,0 +1,72 @@
+//! Drives a window through the headless backend, run with `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{self as ui, Button, Element, Key, MouseButton, Panel, TextBox, Window};
+use std::cell::Cell;
+use std::rc::Rc;
+
+fn center(element: &impl Element) -> (i32, i32) {
+    let bounds = element.bounds().expect("Failed to get bounds");
+    ((bounds.l + bounds.r) / 2, (bounds.t + bounds.b) / 2)
+}
+
+#[test]
+fn click_and_type() {
+    ui::init();
+
+    let window = Window::new("Headless", 300, 100, 0).expect("Failed to create window");
+    let panel = Panel::new(&window, ui::UI_PANEL_GRAY | ui::UI_PANEL_MEDIUM_SPACING)
+        .expect("Failed to create panel");
+    let button =
+        Button::new(&panel, ui::UI_ELEMENT_H_FILL, "Submit").expect("Failed to create button");
+    let textbox = TextBox::new(&panel, ui::UI_ELEMENT_H_FILL).expect("Failed to create textbox");
+    window.resize(300, 100).expect("Failed to lay out window");
+    let parent = button.window().expect("Failed to get window");
+    assert_eq!(parent.map(|parent| parent.id()), Some(window.id()));
+
+    let clicks = Rc::new(Cell::new(0));
+    let counter = clicks.clone();
+    button
+        .invoke(Box::new(move || counter.set(counter.get() + 1)))
+        .expect("Failed to set callback");
+
+    let (x, y) = center(&button);
+    window
+        .click(x, y, MouseButton::Left)
+        .expect("Failed to click");
+    window
+        .click(x, y, MouseButton::Left)
+        .expect("Failed to click");
+    assert_eq!(clicks.get(), 2);
+
+    // Releasing outside the button cancels the click
+    window
+        .mouse_down(x, y, MouseButton::Left)
+        .expect("Failed to press");
+    window
+        .mouse_up(x, 99, MouseButton::Left)
+        .expect("Failed to release");
+    assert_eq!(clicks.get(), 2);
+
+    let (x, y) = center(&textbox);
+    window
+        .click(x, y, MouseButton::Left)
+        .expect("Failed to click");
+    window.type_text("Hello, headless").expect("Failed to type");
+    assert_eq!(
+        textbox.get_text().expect("Failed to get text"),
+        "Hello, headless"
+    );
+
+    window
+        .press_key(Key::Backspace)
+        .expect("Failed to press key");
+    assert_eq!(
+        textbox.get_text().expect("Failed to get text"),
+        "Hello, headles"
+    );
+    assert_eq!(clicks.get(), 2);
+}
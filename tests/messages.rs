@@ -0,0 +1,83 @@
+//! Decoded message payloads from real input on the headless backend, run with
+//! `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{
+    self as ui, Button, Element, Message, MouseButton, Panel, Response, Widget, Window,
+};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+fn center(element: &impl Element) -> (i32, i32) {
+    let bounds = element.bounds().expect("Failed to get bounds");
+    ((bounds.l + bounds.r) / 2, (bounds.t + bounds.b) / 2)
+}
+
+fn count_clicks(button: &Button) -> Rc<Cell<i32>> {
+    let clicks = Rc::new(Cell::new(0));
+    let counter = clicks.clone();
+    button
+        .invoke(Box::new(move || counter.set(counter.get() + 1)))
+        .expect("Failed to set callback");
+    clicks
+}
+
+#[test]
+fn payloads() {
+    ui::init();
+
+    let window = Window::new("Messages", 300, 100, 0).expect("Failed to create window");
+    let outer = Panel::new(&window, ui::UI_PANEL_GRAY).expect("Failed to create panel");
+    let inner = Panel::new(&outer, ui::UI_ELEMENT_H_FILL).expect("Failed to create panel");
+    let first = Button::new(&inner, ui::UI_ELEMENT_H_FILL, "First").expect("Failed to create");
+    let second = Button::new(&inner, ui::UI_ELEMENT_H_FILL, "Second").expect("Failed to create");
+    let first_clicks = count_clicks(&first);
+    let second_clicks = count_clicks(&second);
+
+    let pressed = Rc::new(RefCell::new(Vec::new()));
+    let redirect = Rc::new(Cell::new(false));
+    let (seen, redirecting) = (pressed.clone(), redirect.clone());
+    let (target, client) = (second.handle(), inner.handle());
+    outer
+        .on_message(move |_, message| match message {
+            Message::PressedDescendent(child) => {
+                seen.borrow_mut().push(child.map(|child| child.id()));
+                Response::Unhandled
+            }
+            Message::FindByPoint(mut find) if redirecting.get() => {
+                let target = target.upgrade().expect("Target destroyed");
+                find.set_result(&target).expect("Failed to set result");
+                Response::Handled
+            }
+            Message::ClientParent(mut parent) => {
+                let client = client.upgrade().expect("Client destroyed");
+                parent.set(&client).expect("Failed to set parent");
+                Response::Handled
+            }
+            _ => Response::Unhandled,
+        })
+        .expect("Failed to set handler");
+
+    // New children of the outer panel go to the inner one
+    let third = Button::new(&outer, ui::UI_ELEMENT_H_FILL, "Third").expect("Failed to create");
+    let parent = third.parent().expect("Failed to get parent");
+    assert_eq!(parent.map(|parent| parent.id()), Some(inner.id()));
+    window.resize(300, 100).expect("Failed to lay out window");
+
+    let (x, y) = center(&first);
+    window
+        .click(x, y, MouseButton::Left)
+        .expect("Failed to click");
+    assert_eq!((first_clicks.get(), second_clicks.get()), (1, 0));
+    assert_eq!(*pressed.borrow(), [Some(inner.id())]);
+
+    // Hit testing at the first button finds the second one instead
+    redirect.set(true);
+    window
+        .click(x, y, MouseButton::Left)
+        .expect("Failed to click");
+    assert_eq!((first_clicks.get(), second_clicks.get()), (1, 1));
+}
@@ -0,0 +1,38 @@
+//! Paint statistics on the headless backend, run with `--features headless,debug`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(all(feature = "headless", feature = "debug"))]
+
+use luigi_rs::{self as ui, Button, Element, Panel, Window};
+
+#[test]
+fn filled_panel() {
+    ui::init();
+
+    let window = Window::new("Overdraw", 200, 100, 0).expect("Failed to create window");
+    let panel = Panel::new(&window, ui::UI_PANEL_GRAY).expect("Failed to create panel");
+    let mut button = Button::new(&panel, 0, "Button").expect("Failed to create button");
+    assert_eq!(window.last_overdraw().expect("Window destroyed"), None);
+
+    // The gray panel fills the whole window once, and the button paints on top
+    let full = window.overdraw().expect("Failed to count fills");
+    assert_eq!(full.area, 200 * 100);
+    assert!(full.ratio() >= 1.0, "ratio {}", full.ratio());
+    assert_eq!(
+        window.last_overdraw().expect("Window destroyed"),
+        Some(full)
+    );
+
+    // Repainting the button only repaints its part of the window
+    button.refresh().expect("Failed to refresh button");
+    ui::pump_pending();
+    let bounds = button.bounds().expect("Failed to get bounds");
+    let last = window
+        .last_overdraw()
+        .expect("Window destroyed")
+        .expect("Window did not paint");
+    assert_eq!(last.area, (bounds.width() * bounds.height()) as u64);
+    assert!(last.ratio() >= 1.0, "ratio {}", last.ratio());
+    assert!(last.fill_count < full.fill_count);
+}
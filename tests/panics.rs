@@ -0,0 +1,51 @@
+//! Panicking callbacks on the headless backend, run with `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{self as ui, Button, Element, MouseButton, Widget, Window};
+use std::panic;
+
+#[test]
+fn resumed_from_message_loop() {
+    ui::init();
+
+    let window = Window::new("Panics", 200, 100, 0).expect("Failed to create window");
+    let button = Button::new(&window, 0, "Button").expect("Failed to create button");
+    window.resize(200, 100).expect("Failed to lay out window");
+    button
+        .invoke(Box::new(|| panic!("Button callback panicked")))
+        .expect("Failed to set callback");
+
+    // Click from inside the loop, so the panic crosses Luigi's C frames
+    let bounds = button.bounds().expect("Failed to get bounds");
+    let (x, y) = ((bounds.l + bounds.r) / 2, (bounds.t + bounds.b) / 2);
+    let handle = window.handle();
+    window
+        .sender()
+        .expect("Failed to get sender")
+        .send(move || {
+            let window = handle.upgrade().expect("Window destroyed");
+            window
+                .click(x, y, MouseButton::Left)
+                .expect("Failed to click");
+        })
+        .expect("Failed to send");
+
+    let payload = panic::catch_unwind(ui::message_loop).expect_err("Loop did not panic");
+    assert_eq!(
+        payload.downcast_ref::<&str>(),
+        Some(&"Button callback panicked")
+    );
+
+    // The panic is resumed once, and the UI is still usable afterwards
+    ui::pump_pending();
+    assert!(button.is_alive());
+    button
+        .invoke(Box::new(|| ()))
+        .expect("Failed to set callback");
+    window
+        .click(x, y, MouseButton::Left)
+        .expect("Failed to click");
+}
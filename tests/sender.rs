@@ -0,0 +1,59 @@
+//! Sending work to the UI thread on the headless backend, run with `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{self as ui, Element, Error, Label, Widget, Window};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[test]
+fn from_worker_thread() {
+    ui::init();
+
+    let window = Window::new("Sender", 200, 100, 0).expect("Failed to create window");
+    let label = Label::new(&window, 0, "Waiting").expect("Failed to create label");
+    let sender = window.sender().expect("Failed to get sender");
+    let ui_thread = thread::current().id();
+    let ran = Arc::new(Mutex::new(Vec::new()));
+
+    let (worker_sender, worker_ran) = (sender.clone(), ran.clone());
+    let (label, window_handle) = (label.handle(), window.handle());
+    let worker = thread::spawn(move || {
+        for i in 0..3 {
+            let ran = worker_ran.clone();
+            worker_sender
+                .send(move || {
+                    assert_eq!(thread::current().id(), ui_thread);
+                    let label = label.upgrade().expect("Label destroyed");
+                    label
+                        .set_content(&format!("Result {i}"))
+                        .expect("Failed to set content");
+                    ran.lock().unwrap().push(i);
+                })
+                .expect("Failed to send");
+        }
+        // Destroying the last window ends the message loop
+        worker_sender
+            .send(move || {
+                let mut window = window_handle.upgrade().expect("Window destroyed");
+                window.destroy().expect("Failed to destroy window");
+            })
+            .expect("Failed to send");
+    });
+
+    ui::message_loop();
+    worker.join().expect("Worker panicked");
+    assert_eq!(*ran.lock().unwrap(), [0, 1, 2]);
+
+    // Closures sent after the window is gone are dropped unrun
+    assert!(!window.is_alive());
+    assert!(!sender.is_alive());
+    let late = ran.clone();
+    let result = thread::spawn(move || sender.send(move || late.lock().unwrap().push(3)))
+        .join()
+        .expect("Worker panicked");
+    assert!(matches!(result, Err(Error::Destroyed)));
+    assert_eq!(Arc::strong_count(&ran), 1);
+}
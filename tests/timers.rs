@@ -0,0 +1,76 @@
+//! Timeouts and intervals on the headless backend, run with `--features headless`.
+//!
+//! Luigi's state is global, so everything happens in a single test.
+
+#![cfg(feature = "headless")]
+
+use luigi_rs::{self as ui, Element, Pump, Timer, Window};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn timeouts_and_intervals() {
+    ui::init();
+
+    let mut window = Window::new("Timers", 200, 100, 0).expect("Failed to create window");
+    let start = Instant::now();
+
+    let fired = Rc::new(Cell::new(None));
+    let slot = fired.clone();
+    window
+        .set_timeout(Duration::from_millis(50), move || {
+            slot.set(Some(start.elapsed()))
+        })
+        .expect("Failed to set timeout");
+
+    let ticks = Rc::new(Cell::new(0));
+    let interval = Rc::new(Cell::new(None::<Timer>));
+    let (counter, handle) = (ticks.clone(), interval.clone());
+    let timer = window
+        .set_interval(Duration::from_millis(20), move || {
+            counter.set(counter.get() + 1);
+            if counter.get() == 3 {
+                handle.get().expect("Interval not set").cancel();
+            }
+        })
+        .expect("Failed to set interval");
+    interval.set(Some(timer));
+
+    let cancelled = window
+        .set_timeout(Duration::from_millis(10), || {
+            panic!("Cancelled timeout fired")
+        })
+        .expect("Failed to set timeout");
+    cancelled.cancel();
+    assert!(!cancelled.is_active());
+
+    // Timers do not animate, the loop sleeps until the next one is due
+    assert!(!ui::is_animating());
+    assert!(ui::next_timer().is_some_and(|left| left <= Duration::from_millis(20)));
+
+    let done = window
+        .set_timeout(Duration::from_millis(150), || {})
+        .expect("Failed to set timeout");
+    while done.is_active() {
+        assert_eq!(ui::pump(), Pump::Running);
+    }
+
+    let fired = fired.get().expect("Timeout did not fire");
+    assert!(fired >= Duration::from_millis(50), "fired after {fired:?}");
+    assert!(fired < Duration::from_millis(250), "fired after {fired:?}");
+    assert_eq!(ticks.get(), 3);
+    assert!(!timer.is_active());
+    assert_eq!(ui::next_timer(), None);
+
+    // Destroying the window drops its timers
+    let pending = window
+        .set_timeout(Duration::from_secs(60), || {
+            panic!("Timeout outlived its window")
+        })
+        .expect("Failed to set timeout");
+    window.destroy().expect("Failed to destroy window");
+    ui::pump_pending();
+    assert!(!pending.is_active());
+    assert_eq!(ui::next_timer(), None);
+}
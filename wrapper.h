@@ -1 +1,53 @@
-#include "luigi.h"
\ No newline at end of file
+#include "luigi.h"
+
+// Helpers implemented in src/wrapper.c
+void LuigiRsQuit();
+UITheme LuigiRsGetTheme();
+void LuigiRsSetTheme(UITheme theme);
+UITheme LuigiRsThemeClassic();
+UITheme LuigiRsThemeDark();
+void LuigiRsFontFree(UIFont *font);
+void LuigiRsSetTimers(int (*timers)(bool *fired));
+int LuigiRsMessageLoop();
+bool LuigiRsMessageLoopSingle(bool onlyPending, int *result);
+bool LuigiRsIsAnimating();
+int LuigiRsConnectionNumber();
+UIElement *LuigiRsDialogBegin(UIWindow *window);
+void LuigiRsDialogOpen(UIWindow *window, UIElement *focus);
+bool LuigiRsDialogRun(UIWindow *window, UIElement *focus);
+void LuigiRsDialogClose();
+void LuigiRsDialogEnd(UIWindow *window);
+void LuigiRsClipboardWriteText(UIWindow *window, const char *text);
+char *LuigiRsClipboardReadTextStart(UIWindow *window, size_t *bytes);
+void LuigiRsClipboardReadTextEnd(UIWindow *window, char *text);
+UIWindow *LuigiRsWindows();
+void LuigiRsWindowCapture(UIWindow *window);
+
+#ifdef UI_HEADLESS
+// Input synthesis for the headless backend, implemented in src/headless.c
+bool LuigiRsHeadlessPending();
+bool LuigiRsHeadlessWait(int timeout);
+void LuigiRsHeadlessResize(UIWindow *window, int width, int height);
+void LuigiRsHeadlessMouse(UIWindow *window, int x, int y, UIMessage message, int di);
+void LuigiRsHeadlessKey(UIWindow *window, int code, const char *text, int textBytes);
+void LuigiRsHeadlessModifiers(UIWindow *window, bool ctrl, bool shift, bool alt);
+void LuigiRsHeadlessClose(UIWindow *window);
+void LuigiRsHeadlessDropFiles(UIWindow *window, int count, char **files);
+#endif
+
+#ifdef UI_DEBUG
+bool LuigiRsInspectorLog(const char *text);
+void LuigiRsWindowCountFills(UIWindow *window);
+bool LuigiRsWindowLastFills(UIWindow *window, int *fillCount, int *area);
+#endif
+
+#ifdef UI_AUTOMATION_TESTS
+// Luigi's automation harness, which luigi.h only declares in its implementation
+void UIAutomationProcessMessage();
+void UIAutomationKeyboardTypeSingle(intptr_t code, bool ctrl, bool shift, bool alt);
+void UIAutomationKeyboardType(const char *string);
+bool UIAutomationCheckCodeLineMatches(UICode *code, int lineIndex, const char *input);
+bool UIAutomationCheckTableItemMatches(UITable *table, int row, int column, const char *input);
+void LuigiRsAutomationSetTests(int (*tests)(void));
+bool LuigiRsAutomationClick(UIElement *element);
+#endif